@@ -0,0 +1,170 @@
+//! Compares per-tick allocations of `BestPath::shortest_path` (fresh distance
+//! grid, predecessor map, and heap every call) against `PathBuffers::shortest_path`
+//! (same buffers reused across calls) on the 100x100 all-Grass world `main.rs`
+//! demos, since that's the exact difference `PathBuffers` was introduced for.
+//!
+//! Wall-clock time alone hides most of the effect (both are O(nodes log nodes)
+//! either way), so this counts actual allocator calls with a wrapping global
+//! allocator instead of relying on `Criterion`'s timing.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use holy_crab_best_path::{BestPath, PathBuffers};
+use robotics_lib::energy::Energy;
+use robotics_lib::event::events::Event;
+use robotics_lib::runner::backpack::BackPack;
+use robotics_lib::runner::{Robot, Runnable, Runner};
+use robotics_lib::world::coordinates::Coordinate;
+use robotics_lib::world::environmental_conditions::EnvironmentalConditions;
+use robotics_lib::world::environmental_conditions::WeatherType::{Rainy, Sunny};
+use robotics_lib::world::tile::TileType::Grass;
+use robotics_lib::world::tile::{Content, Tile};
+use robotics_lib::world::world_generator::Generator;
+use robotics_lib::world::World;
+
+const WORLD_SIZE: usize = 100;
+
+static TICK_ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        TICK_ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+struct FlatGrassGenerator;
+
+impl Generator for FlatGrassGenerator {
+    fn gen(&mut self) -> (Vec<Vec<Tile>>, (usize, usize), EnvironmentalConditions, f32, Option<HashMap<Content, f32>>) {
+        let mut map: Vec<Vec<Tile>> = Vec::new();
+        for _ in 0..WORLD_SIZE {
+            let mut row: Vec<Tile> = Vec::new();
+            for _ in 0..WORLD_SIZE {
+                row.push(Tile { tile_type: Grass, content: Content::None, elevation: 0 });
+            }
+            map.push(row);
+        }
+        let environmental_conditions = EnvironmentalConditions::new(&[Sunny, Rainy], 15, 12).unwrap();
+        (map, (0, 0), environmental_conditions, 1.0, None)
+    }
+}
+
+macro_rules! impl_runnable_boilerplate {
+    ($robot:ty) => {
+        impl Runnable for $robot {
+            fn handle_event(&mut self, _event: Event) {}
+            fn get_energy(&self) -> &Energy {
+                &self.robot.energy
+            }
+            fn get_energy_mut(&mut self) -> &mut Energy {
+                &mut self.robot.energy
+            }
+            fn get_coordinate(&self) -> &Coordinate {
+                &self.robot.coordinate
+            }
+            fn get_coordinate_mut(&mut self) -> &mut Coordinate {
+                &mut self.robot.coordinate
+            }
+            fn get_backpack(&self) -> &BackPack {
+                &self.robot.backpack
+            }
+            fn get_backpack_mut(&mut self) -> &mut BackPack {
+                &mut self.robot.backpack
+            }
+        }
+    };
+}
+
+/// Calls `BestPath::shortest_path` every tick: a fresh distance grid,
+/// predecessor map, and heap are allocated from scratch each time.
+struct FreshAllocRobot {
+    robot: Robot,
+}
+
+impl Runnable for FreshAllocRobot {
+    fn process_tick(&mut self, world: &mut World) {
+        let nodi_conosciuti: Vec<((i32, i32), Tile)> = vec![];
+        let nodi_interesse: Vec<(i32, i32)> = vec![(WORLD_SIZE as i32 - 1, WORLD_SIZE as i32 - 1)];
+        let _ = BestPath::shortest_path(self, world, &nodi_conosciuti, nodi_interesse, (0, 0), true);
+    }
+}
+impl_runnable_boilerplate!(FreshAllocRobot);
+
+/// Calls `PathBuffers::shortest_path` every tick, reusing the same buffers
+/// instead of allocating them fresh.
+struct ReusedBuffersRobot {
+    robot: Robot,
+    buffers: PathBuffers,
+}
+
+impl Runnable for ReusedBuffersRobot {
+    fn process_tick(&mut self, world: &mut World) {
+        let nodi_conosciuti: Vec<((i32, i32), Tile)> = vec![];
+        let nodi_interesse: Vec<(i32, i32)> = vec![(WORLD_SIZE as i32 - 1, WORLD_SIZE as i32 - 1)];
+        let buffers = std::mem::replace(&mut self.buffers, PathBuffers::with_capacity(0, 0));
+        let mut buffers = buffers;
+        let _ = buffers.shortest_path(self, world, &nodi_conosciuti, nodi_interesse, (0, 0), true);
+        self.buffers = buffers;
+    }
+}
+impl_runnable_boilerplate!(ReusedBuffersRobot);
+
+/// Runs `iters` ticks through a real `Runner`, returning the number of
+/// allocator calls made across all of them together with the total elapsed
+/// time (the latter only to satisfy `Criterion::iter_custom`'s signature).
+fn run_ticks(run: Result<Runner, String>, iters: u64) -> (Duration, usize) {
+    let before = TICK_ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    if let Ok(mut runner) = run {
+        for _ in 0..iters {
+            let _ = runner.game_tick();
+        }
+    }
+    let elapsed = start.elapsed();
+    let after = TICK_ALLOCATIONS.load(Ordering::Relaxed);
+    (elapsed, after - before)
+}
+
+fn bench_shortest_path_allocations(c: &mut Criterion) {
+    let mut fresh_alloc_total = 0usize;
+    c.bench_function("shortest_path_fresh_alloc_100x100", |b| {
+        b.iter_custom(|iters| {
+            let mut generator = FlatGrassGenerator;
+            let robot = FreshAllocRobot { robot: Robot::new() };
+            let run = Runner::new(Box::new(robot), &mut generator);
+            let (elapsed, allocations) = run_ticks(run, iters);
+            fresh_alloc_total += allocations;
+            elapsed
+        });
+    });
+    println!("shortest_path_fresh_alloc_100x100: {fresh_alloc_total} allocator calls");
+
+    let mut reused_buffers_total = 0usize;
+    c.bench_function("shortest_path_reused_buffers_100x100", |b| {
+        b.iter_custom(|iters| {
+            let mut generator = FlatGrassGenerator;
+            let robot = ReusedBuffersRobot { robot: Robot::new(), buffers: PathBuffers::with_capacity(WORLD_SIZE, WORLD_SIZE) };
+            let run = Runner::new(Box::new(robot), &mut generator);
+            let (elapsed, allocations) = run_ticks(run, iters);
+            reused_buffers_total += allocations;
+            elapsed
+        });
+    });
+    println!("shortest_path_reused_buffers_100x100: {reused_buffers_total} allocator calls");
+}
+
+criterion_group!(benches, bench_shortest_path_allocations);
+criterion_main!(benches);
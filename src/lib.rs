@@ -4,11 +4,13 @@ use std::vec::Vec;
 use robotics_lib::world::tile::{Tile, TileType, Content};
 use robotics_lib::interface::Direction;
 use std::collections::{BinaryHeap};
-use std::cmp::Ordering;
+use std::cmp::{Ordering, Reverse};
 use robotics_lib::interface::discover_tiles;
-use robotics_lib::interface::Tools;
+use robotics_lib::interface::{look_at_sky, Tools};
 use robotics_lib::world::World;
+use robotics_lib::world::environmental_conditions::WeatherType;
 use robotics_lib::runner::Runnable;
+use crossbeam_channel::Sender;
 
 pub struct BestPath{
 
@@ -18,6 +20,128 @@ impl Tools for BestPath{
 
 }
 
+/// Selects which algorithm `BestPath` uses to expand the search graph.
+///
+/// `Dijkstra` is the original uninformed expansion and remains the default; it is
+/// the right choice whenever there is more than one target, since a single full
+/// expansion from the start already yields every target's shortest path. `AStar`
+/// is goal-directed, which is much cheaper on large worlds: with exactly one
+/// target it searches straight toward it, and with several it visits them one
+/// leg at a time, each leg aimed at whichever remaining target is nearest. `Jps`
+/// is Jump Point Search: it assumes every walkable tile costs the same to enter
+/// (it searches the raw grid, ignoring terrain/weather cost) and skips long
+/// straight corridors instead of expanding every tile on them, so it only pays
+/// off on open, uniform-cost grids; like `AStar` it's single-target only and
+/// multi-target calls fall back to `Dijkstra`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchStrategy {
+    Dijkstra,
+    AStar,
+    Jps,
+}
+
+/// How `from_vec_to_matrix` decides which unknown cells to discover when
+/// `discover` is `true`.
+///
+/// `Exhaustive` is the original behavior and remains the default: every
+/// unknown cell without a known walkable neighbor gets its own individual
+/// `discover_tiles` call, which is simple but triggers enormous numbers of
+/// single-cell discoveries on a large bounding box with sparse known tiles.
+/// `Frontier` instead grows outward one batch at a time: it computes the set
+/// of unknown cells Von-Neumann-adjacent to a known walkable cell (the
+/// exploration frontier), discovers that whole batch in a single
+/// `discover_tiles` call, and repeats only until `starting_node` is connected
+/// to every node of interest — interior cells no path could possibly cross
+/// are never discovered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiscoveryStrategy {
+    Exhaustive,
+    Frontier,
+}
+
+/// How often, in expanded nodes, `dijkstra_with_progress` reports a
+/// `SearchEvent::NodesExpanded` update.
+const PROGRESS_REPORT_INTERVAL: usize = 500;
+
+/// Structured updates emitted during a `shortest_path_with_progress` call so a
+/// caller can drive a progress bar or decide to abort early, instead of
+/// blocking on the whole search with no feedback.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SearchEvent {
+    /// Sent every `PROGRESS_REPORT_INTERVAL` nodes popped off the frontier.
+    NodesExpanded(usize),
+    /// Sent the moment a requested node of interest is popped, i.e. its shortest
+    /// distance from the current leg's start is now final.
+    InterestReached { target: (i32, i32), total_cost: i32 },
+    /// Sent once after every reachable target has been visited (or the node
+    /// budget, if any, was exhausted first).
+    Finished { total_cost: i32 },
+}
+
+/// Pheromone levels below this value are dropped instead of kept around forever,
+/// the same way a real trail eventually evaporates to nothing.
+const PHEROMONE_EPSILON: f32 = 0.01;
+/// Fraction of a pheromone level retained after one `PheromoneMap::decay` call.
+const PHEROMONE_DECAY: f32 = 0.9;
+/// Pheromone deposited on the frontier tile chosen by `BestPath::explore_toward`.
+const PHEROMONE_DEPOSIT: f32 = 1.0;
+
+/// Per-tile pheromone trail used to bias frontier exploration toward directions
+/// that have recently made progress, inspired by ant-colony foraging.
+///
+/// Callers own one `PheromoneMap` per robot and pass it into
+/// `BestPath::explore_toward` every tick so deposits and decay persist across
+/// calls.
+#[derive(Debug, Clone, Default)]
+pub struct PheromoneMap {
+    levels: HashMap<(i32, i32), f32>,
+}
+
+impl PheromoneMap {
+    pub fn new() -> Self {
+        PheromoneMap { levels: HashMap::new() }
+    }
+
+    /// Evaporates every deposited level by `PHEROMONE_DECAY`, dropping entries
+    /// that fall below `PHEROMONE_EPSILON`. Call once per tick, before any new
+    /// deposit, so stale trails fade out instead of accumulating forever.
+    pub fn decay(&mut self) {
+        for level in self.levels.values_mut() {
+            *level *= PHEROMONE_DECAY;
+        }
+        self.levels.retain(|_, level| *level > PHEROMONE_EPSILON);
+    }
+
+    fn deposit(&mut self, pos: (i32, i32), amount: f32) {
+        *self.levels.entry(pos).or_insert(0.0) += amount;
+    }
+
+    fn level(&self, pos: (i32, i32)) -> f32 {
+        *self.levels.get(&pos).unwrap_or(&0.0)
+    }
+}
+
+/// Snapshot of the map a [`BestPath`] call assembled in order to answer a
+/// query, returned by [`BestPath::shortest_path_with_report`] so discoveries
+/// aren't thrown away once the path is computed.
+///
+/// `matrix`/`offset` are the same assembled map and `(minx, miny)` world
+/// offset `shortest_path` already builds internally; `walkables` is a
+/// row-major walkable mask over that same `width x height` grid, handy for a
+/// caller's own visualization or planning. `newly_discovered` lists exactly
+/// the cells this call paid to discover via `discover_tiles` — feeding them
+/// back in as `nodi_conosciuti` on the next call lets the robot avoid ever
+/// re-discovering the same tile.
+#[derive(Debug, Clone)]
+pub struct ExplorationReport {
+    pub width: usize,
+    pub height: usize,
+    pub walkables: Vec<bool>,
+    pub matrix: Vec<Vec<Tile>>,
+    pub offset: (i32, i32),
+    pub newly_discovered: Vec<((i32, i32), Tile)>,
+}
+
 impl BestPath{
     /// Computes the shortest path for a robot through a given world.
     ///
@@ -157,23 +281,7 @@ impl BestPath{
     /// ```
     ///
     pub fn shortest_path(robot: &mut impl Runnable, world: &mut World, nodi_conosciuti: &Vec<((i32, i32), Tile)>, nodi_interesse: Vec<(i32, i32)>, starting_node: (i32, i32),discover: bool)->Vec<Vec<Direction>> {
-        // swap x and y due to error
-        let mut nodi_interesse_swapped: Vec<(i32, i32)> = vec![];
-        for nodo in nodi_interesse{
-            nodi_interesse_swapped.push((nodo.1, nodo.0));
-        }
-        let mut corrected_starting_node = (starting_node.0, starting_node.1);
-
-        let (matrix, minx, miny) = from_vec_to_matrix(robot, world, nodi_conosciuti, &nodi_interesse_swapped, corrected_starting_node, discover);
-        // rimuovo l'offset dai nodi d'interesse
-        let mut nodi_interesse_corrected: Vec<(i32, i32)> = vec![];
-        for nodo in nodi_interesse_swapped{
-            nodi_interesse_corrected.push((nodo.0 - minx, nodo.1 - miny));
-        }
-
-        let coordinates = get_coordinates(&matrix);
-        corrected_starting_node = (corrected_starting_node.1 - minx, corrected_starting_node.0-miny);
-        let (matrix_node, mut target_nodes, starting_node) = change_matrix(matrix.clone(), nodi_interesse_corrected, corrected_starting_node);
+        let (_matrix, _minx, _miny, _discovered, coordinates, matrix_node, mut target_nodes, starting_node) = prepare_query(robot, world, nodi_conosciuti, nodi_interesse, starting_node, discover, DiscoveryStrategy::Exhaustive, CostMode::Energy);
 
         target_nodes = find_connected_targets(&matrix_node, starting_node, &target_nodes);
 
@@ -187,6 +295,782 @@ impl BestPath{
 
         return results;
     }
+
+    /// Same as [`BestPath::shortest_path`], but instead of greedily visiting
+    /// whichever target is cheapest from wherever the robot currently stands,
+    /// computes the visiting order that minimizes the total cost of the whole
+    /// tour (exact Held-Karp for up to `HELD_KARP_LIMIT` targets, nearest-neighbor
+    /// plus 2-opt local search above that) and returns the per-target
+    /// `Vec<Direction>` legs in that order instead. Use this over
+    /// [`BestPath::shortest_path`] whenever `nodi_interesse` has more than a
+    /// couple of entries and the total distance travelled matters more than
+    /// always moving toward the nearest thing next.
+    pub fn shortest_path_optimized_order(robot: &mut impl Runnable, world: &mut World, nodi_conosciuti: &Vec<((i32, i32), Tile)>, nodi_interesse: Vec<(i32, i32)>, starting_node: (i32, i32), discover: bool) -> Vec<Vec<Direction>> {
+        let (_matrix, _minx, _miny, _discovered, coordinates, matrix_node, target_nodes, start) = prepare_query(robot, world, nodi_conosciuti, nodi_interesse, starting_node, discover, DiscoveryStrategy::Exhaustive, CostMode::Energy);
+
+        let target_nodes = find_connected_targets(&matrix_node, start, &target_nodes);
+
+        match build_path_optimized(&matrix_node, start, target_nodes, &coordinates) {
+            Ok(paths) => paths,
+            Err(_) => vec![vec![]],
+        }
+    }
+
+    /// Same as [`BestPath::shortest_path`], but lets the caller pick the search
+    /// algorithm via `strategy`.
+    ///
+    /// `SearchStrategy::AStar` uses the Manhattan distance to the target(s),
+    /// scaled by the cheapest walkable tile cost, as an admissible heuristic, so
+    /// it expands far fewer nodes than Dijkstra while still returning the
+    /// optimal path. With a single node of interest this runs `astar` straight
+    /// to that goal; with several, `build_path_astar` visits them one leg at a
+    /// time the same way `build_path` does, but uses the minimum distance to any
+    /// still-unvisited target as the per-leg heuristic instead of a full
+    /// Dijkstra sweep.
+    pub fn shortest_path_with_strategy(robot: &mut impl Runnable, world: &mut World, nodi_conosciuti: &Vec<((i32, i32), Tile)>, nodi_interesse: Vec<(i32, i32)>, starting_node: (i32, i32), discover: bool, strategy: SearchStrategy) -> Vec<Vec<Direction>> {
+        let (matrix, _minx, _miny, _discovered, coordinates, matrix_node, target_nodes, start) = prepare_query(robot, world, nodi_conosciuti, nodi_interesse, starting_node, discover, DiscoveryStrategy::Exhaustive, CostMode::Energy);
+
+        let target_nodes = find_connected_targets(&matrix_node, start, &target_nodes);
+
+        if strategy == SearchStrategy::AStar && target_nodes.len() == 1 {
+            let min_step_cost = min_step_cost(&matrix);
+            let goal = target_nodes[0];
+            let cols = matrix[0].len();
+            let (distance, predecessor) = astar(&matrix_node, start, goal, cols, min_step_cost);
+            let path = reconstruct_shortest_path(predecessor, goal);
+            return match path {
+                Some(nodes) => match path_to_directions(&coordinates, &nodes) {
+                    Ok(directions) => vec![directions],
+                    Err(_) => vec![vec![]],
+                },
+                None => {
+                    let _ = distance;
+                    vec![vec![]]
+                }
+            };
+        }
+
+        if strategy == SearchStrategy::Jps && target_nodes.len() == 1 {
+            let cols = matrix[0].len();
+            let goal = target_nodes[0];
+            let (_distance, predecessor) = find_shortest_paths_jps(&matrix, start, goal, cols);
+            let path = reconstruct_shortest_path(predecessor, goal).map(|jump_points| expand_jump_points(&jump_points, cols));
+            return match path {
+                Some(nodes) => match path_to_directions(&coordinates, &nodes) {
+                    Ok(directions) => vec![directions],
+                    Err(_) => vec![vec![]],
+                },
+                None => vec![vec![]],
+            };
+        }
+
+        if strategy == SearchStrategy::AStar {
+            let min_step_cost = min_step_cost(&matrix);
+            let cols = matrix[0].len();
+            return match build_path_astar(&matrix_node, start, target_nodes, &coordinates, cols, min_step_cost) {
+                Ok(paths) => paths,
+                Err(_) => vec![vec![]],
+            };
+        }
+
+        match build_path(&matrix_node, start, target_nodes, &coordinates) {
+            Ok(paths) => paths,
+            Err(_) => vec![vec![]],
+        }
+    }
+
+    /// Same as [`BestPath::shortest_path`], but lets the caller pick what an edge
+    /// weight represents via `cost_mode` and optionally cap how expensive a
+    /// target is allowed to be via `max_budget`.
+    ///
+    /// Any node of interest whose cheapest path costs more than `max_budget` is
+    /// dropped before the caller sees it, exactly like an unreachable target is
+    /// already dropped by `find_connected_targets`. Every surviving target comes
+    /// back paired with its `total_cost`, so the robot can tell which ones are
+    /// actually affordable before committing to a move.
+    pub fn shortest_path_with_budget(robot: &mut impl Runnable, world: &mut World, nodi_conosciuti: &Vec<((i32, i32), Tile)>, nodi_interesse: Vec<(i32, i32)>, starting_node: (i32, i32), discover: bool, cost_mode: CostMode, max_budget: Option<i32>) -> Vec<(Vec<Direction>, i32)> {
+        let (_matrix, _minx, _miny, _discovered, coordinates, matrix_node, target_nodes, start) = prepare_query(robot, world, nodi_conosciuti, nodi_interesse, starting_node, discover, DiscoveryStrategy::Exhaustive, cost_mode);
+
+        let target_nodes = find_connected_targets(&matrix_node, start, &target_nodes);
+
+        let mut results = Vec::new();
+        for path_result in find_shortest_paths_with_budget(&matrix_node, start, &target_nodes, max_budget) {
+            if let Some(nodes) = path_result.path {
+                if let Ok(directions) = path_to_directions(&coordinates, &nodes) {
+                    results.push((directions, path_result.total_cost));
+                }
+            }
+        }
+
+        results
+    }
+
+    /// Same as [`BestPath::shortest_path`], but also returns an
+    /// [`ExplorationReport`] of the map assembled to answer the query, instead
+    /// of throwing away the tiles discovered along the way, and lets the caller
+    /// pick how those tiles get discovered via `discovery_strategy`.
+    ///
+    /// `DiscoveryStrategy::Exhaustive` is the original one-cell-at-a-time
+    /// behavior; `DiscoveryStrategy::Frontier` instead grows the known map
+    /// outward in batches and stops as soon as `starting_node` is connected to
+    /// every node of interest, which pays for far fewer `discover_tiles` calls
+    /// on a large, mostly-unknown map.
+    ///
+    /// Feeding `report.newly_discovered` back in as `nodi_conosciuti` on the
+    /// caller's next call means the robot never pays to rediscover the same
+    /// tile twice.
+    pub fn shortest_path_with_report(robot: &mut impl Runnable, world: &mut World, nodi_conosciuti: &Vec<((i32, i32), Tile)>, nodi_interesse: Vec<(i32, i32)>, starting_node: (i32, i32), discover: bool, discovery_strategy: DiscoveryStrategy) -> (Vec<Vec<Direction>>, ExplorationReport) {
+        let (matrix, minx, miny, newly_discovered, coordinates, matrix_node, target_nodes, start) = prepare_query(robot, world, nodi_conosciuti, nodi_interesse, starting_node, discover, discovery_strategy, CostMode::Energy);
+
+        let target_nodes = find_connected_targets(&matrix_node, start, &target_nodes);
+
+        let paths = match build_path(&matrix_node, start, target_nodes, &coordinates) {
+            Ok(paths) => paths,
+            Err(_) => vec![vec![]],
+        };
+
+        let height = matrix.len();
+        let width = if height == 0 { 0 } else { matrix[0].len() };
+        let walkables = matrix.iter().flatten().map(is_wakable).collect();
+
+        let report = ExplorationReport {
+            width,
+            height,
+            walkables,
+            matrix,
+            offset: (minx, miny),
+            newly_discovered,
+        };
+
+        (paths, report)
+    }
+
+    /// Computes the shortest-path cost between every pair of `starting_node` and
+    /// `nodi_interesse` at once via `pairwise_distances`, instead of walking
+    /// each pair independently with `dijkstra`.
+    ///
+    /// This is target-set analytics, not routing: it doesn't pick a visiting
+    /// order (see [`BestPath::shortest_tour`] for that) or a single destination
+    /// (see [`BestPath::shortest_path`]) — it just hands back the full cost
+    /// matrix so a caller can e.g. cluster nodes of interest by mutual
+    /// reachability.
+    ///
+    /// # Returns
+    ///
+    /// A square matrix where row/column `0` is `starting_node` and the
+    /// remaining rows/columns follow `nodi_interesse` in order; entry `[i][j]`
+    /// is `None` if that pair isn't connected (including the all-walls case).
+    pub fn pairwise_target_distances(robot: &mut impl Runnable, world: &mut World, nodi_conosciuti: &Vec<((i32, i32), Tile)>, nodi_interesse: Vec<(i32, i32)>, starting_node: (i32, i32), discover: bool) -> Vec<Vec<Option<i32>>> {
+        let requested = nodi_interesse.clone();
+        let (matrix, minx, miny, _discovered, _coordinates, matrix_node, target_nodes, start) = prepare_query(robot, world, nodi_conosciuti, nodi_interesse, starting_node, discover, DiscoveryStrategy::Exhaustive, CostMode::Energy);
+
+        // riordino i target secondo `nodi_interesse`, invece dell'ordine riga per riga di `change_matrix`
+        let cols = matrix[0].len();
+        let mut query_nodes = vec![start];
+        for &(a, b) in &requested {
+            let index = (b - minx) as usize * cols + (a - miny) as usize;
+            if target_nodes.contains(&index) {
+                query_nodes.push(index);
+            }
+        }
+
+        pairwise_distances(&matrix_node, &query_nodes)
+    }
+
+    /// Computes the cheapest order in which to visit every node of interest, then
+    /// concatenates the per-leg paths into a single route.
+    ///
+    /// Unlike [`BestPath::shortest_path`], which returns one independent path per
+    /// target, `shortest_tour` treats `nodi_interesse` as a set that must *all* be
+    /// visited and finds the visiting order that minimizes total energy cost. For
+    /// up to `HELD_KARP_LIMIT` targets this is solved exactly with Held-Karp
+    /// dynamic programming over the pairwise distances between the start and every
+    /// target; above that limit it falls back to nearest-neighbor so the call
+    /// still returns in reasonable time on large target sets.
+    ///
+    /// # Arguments
+    ///
+    /// Same as [`BestPath::shortest_path`].
+    ///
+    /// # Returns
+    ///
+    /// A tuple of the targets in visiting order (in the caller's original `(i32,
+    /// i32)` coordinates) and the concatenated `Vec<Direction>` path that visits
+    /// them in that order. Returns `(vec![], vec![])` if no target is reachable.
+    pub fn shortest_tour(robot: &mut impl Runnable, world: &mut World, nodi_conosciuti: &Vec<((i32, i32), Tile)>, nodi_interesse: Vec<(i32, i32)>, starting_node: (i32, i32), discover: bool) -> (Vec<(i32, i32)>, Vec<Direction>) {
+        let (_matrix, minx, miny, _discovered, coordinates, matrix_node, target_nodes, start) = prepare_query(robot, world, nodi_conosciuti, nodi_interesse, starting_node, discover, DiscoveryStrategy::Exhaustive, CostMode::Energy);
+
+        let target_nodes = find_connected_targets(&matrix_node, start, &target_nodes);
+
+        if target_nodes.is_empty() {
+            return (vec![], vec![]);
+        }
+
+        let (order, node_path) = match held_karp_tour(&matrix_node, start, &target_nodes) {
+            Some(result) => result,
+            None => return (vec![], vec![]),
+        };
+
+        let directions = match path_to_directions(&coordinates, &node_path) {
+            Ok(d) => d,
+            Err(_) => return (vec![], vec![]),
+        };
+
+        // rimappo gli indici dei nodi visitati sulle coordinate originali passate dall'utente
+        let mut ordered_targets: Vec<(i32, i32)> = vec![];
+        for node in order {
+            if let Some((row, col)) = coordinates.get(&node) {
+                let world_x = *row as i32 + minx;
+                let world_y = *col as i32 + miny;
+                ordered_targets.push((world_y, world_x));
+            }
+        }
+
+        (ordered_targets, directions)
+    }
+
+    /// Picks the robot's next move toward `target` when `target` may lie in
+    /// unmapped territory, biasing exploration with a decaying pheromone trail
+    /// instead of re-exploring the same dead ends every tick.
+    ///
+    /// If `target` is already in `known_nodes`, this just steps along the
+    /// shortest known path to it. Otherwise it looks at the exploration
+    /// frontier — known, walkable tiles that border at least one unknown cell —
+    /// and picks the one that best combines a greedy distance-to-target bias
+    /// with the pheromone `pheromones` holds for that tile, then steps
+    /// toward it. The chosen frontier tile is deposited with fresh pheromone so
+    /// repeated ticks converge on productive directions; callers should call
+    /// `pheromones.decay()` once per tick (this function does not do it for
+    /// them, since several calls may happen within the same tick).
+    ///
+    /// Returns `None` if the robot's current position is not itself known or no
+    /// frontier tile can make progress.
+    pub fn explore_toward(robot: &impl Runnable, known_nodes: &Vec<((i32, i32), Tile)>, target: (i32, i32), pheromones: &mut PheromoneMap) -> Option<Direction> {
+        let current = {
+            let coordinate = robot.get_coordinate();
+            (coordinate.get_row() as i32, coordinate.get_col() as i32)
+        };
+
+        let known: HashMap<(i32, i32), Tile> = known_nodes.iter().cloned().collect();
+
+        if !known.contains_key(&current) {
+            return None;
+        }
+
+        if known.contains_key(&target) {
+            if let Some(direction) = step_toward_known(&known, current, target) {
+                return Some(direction);
+            }
+        }
+
+        // individuo la frontiera: celle note e camminabili adiacenti ad almeno una cella sconosciuta
+        let mut frontier: Vec<(i32, i32)> = vec![];
+        for (&(x, y), tile) in known.iter() {
+            if !is_wakable(tile) {
+                continue;
+            }
+            let von_neumann = [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)];
+            if von_neumann.iter().any(|pos| !known.contains_key(pos)) {
+                frontier.push((x, y));
+            }
+        }
+
+        let best = frontier.into_iter().min_by(|a, b| {
+            frontier_score(*a, target, pheromones).partial_cmp(&frontier_score(*b, target, pheromones)).unwrap_or(Ordering::Equal)
+        })?;
+
+        pheromones.deposit(best, PHEROMONE_DEPOSIT);
+
+        step_toward_known(&known, current, best)
+    }
+
+    /// Same as [`BestPath::shortest_path`], but reports progress through an
+    /// optional `crossbeam_channel::Sender` while the search runs, and can stop
+    /// early once `node_budget` nodes have been expanded.
+    ///
+    /// `progress` receives a [`SearchEvent`] every `PROGRESS_REPORT_INTERVAL`
+    /// nodes expanded, each time a node of interest is reached, and once more
+    /// when the whole call finishes — so a caller can drive a progress bar or
+    /// close the receiving end to signal it no longer cares about updates
+    /// (send errors are ignored). `node_budget` caps how many nodes a single
+    /// leg's Dijkstra expansion is allowed to pop before giving up on any target
+    /// not yet reached; with `None` the search always runs to completion, same
+    /// as `shortest_path`. Unreachable (or over-budget) targets are silently
+    /// dropped, exactly like `shortest_path` already does via
+    /// `find_connected_targets`.
+    pub fn shortest_path_with_progress(robot: &mut impl Runnable, world: &mut World, nodi_conosciuti: &Vec<((i32, i32), Tile)>, nodi_interesse: Vec<(i32, i32)>, starting_node: (i32, i32), discover: bool, progress: Option<Sender<SearchEvent>>, node_budget: Option<usize>) -> Vec<Vec<Direction>> {
+        let (_matrix, minx, miny, _discovered, coordinates, matrix_node, target_nodes, start) = prepare_query(robot, world, nodi_conosciuti, nodi_interesse, starting_node, discover, DiscoveryStrategy::Exhaustive, CostMode::Energy);
+
+        let target_nodes = find_connected_targets(&matrix_node, start, &target_nodes);
+
+        let (paths, total_cost) = build_path_with_progress(&matrix_node, start, target_nodes, &coordinates, (minx, miny), progress.as_ref(), node_budget);
+
+        if let Some(tx) = &progress {
+            let _ = tx.send(SearchEvent::Finished { total_cost });
+        }
+
+        if paths.is_empty() {
+            vec![vec![]]
+        } else {
+            paths
+        }
+    }
+
+    /// Returns a reusable [`PathBuffers`] handle sized for a `width x height`
+    /// world. Keep one per robot and call `PathBuffers::shortest_path` on it
+    /// every tick instead of `BestPath::shortest_path`: the distance grid,
+    /// predecessor map, and heap are reused across calls instead of being
+    /// allocated from scratch each tick.
+    pub fn with_capacity(width: usize, height: usize) -> PathBuffers {
+        let len = width * height;
+        PathBuffers {
+            distance: vec![None; len],
+            predecessor: vec![None; len],
+            visited: vec![false; len],
+            heap: BinaryHeap::with_capacity(len),
+            path_buffer: Vec::with_capacity(PATH_BUFFER_INITIAL_CAPACITY),
+        }
+    }
+}
+
+/// Initial capacity of a `PathBuffers`' reconstructed-path scratch vector; most
+/// per-tick queries are short hops, so this avoids a reallocation for the common
+/// case while still growing for longer ones.
+const PATH_BUFFER_INITIAL_CAPACITY: usize = 32;
+
+/// Preallocated search state for `BestPath::shortest_path`, reused across calls
+/// instead of allocating a fresh distance grid, predecessor map, and heap every
+/// tick. Obtain one via `BestPath::with_capacity`.
+pub struct PathBuffers {
+    distance: Vec<Option<i32>>,
+    predecessor: Vec<Option<usize>>,
+    visited: Vec<bool>,
+    heap: BinaryHeap<Node>,
+    path_buffer: Vec<usize>,
+}
+
+impl PathBuffers {
+    /// Grows the buffers to fit `len` nodes, if they are not already that big;
+    /// never shrinks, so a smaller query after a larger one still reuses the
+    /// larger allocation.
+    fn ensure_capacity(&mut self, len: usize) {
+        if self.distance.len() < len {
+            self.distance.resize(len, None);
+            self.predecessor.resize(len, None);
+            self.visited.resize(len, false);
+        }
+    }
+
+    /// Clears the buffers for a fresh search over a graph of `len` nodes,
+    /// growing them first if needed.
+    fn reset(&mut self, len: usize) {
+        self.ensure_capacity(len);
+        for slot in &mut self.distance[..len] {
+            *slot = None;
+        }
+        for slot in &mut self.predecessor[..len] {
+            *slot = None;
+        }
+        for slot in &mut self.visited[..len] {
+            *slot = false;
+        }
+        self.heap.clear();
+        self.path_buffer.clear();
+    }
+
+    /// Same as the free `dijkstra` function, but writes into this handle's
+    /// buffers instead of allocating new ones.
+    fn dijkstra(&mut self, graph: &Vec<Vec<Node>>, start: usize) {
+        self.reset(graph.len());
+
+        self.distance[start] = Some(0);
+        self.heap.push(Node { index: start, distance: 0 });
+
+        while let Some(Node { index, distance: dist }) = self.heap.pop() {
+            if self.visited[index] {
+                continue;
+            }
+            self.visited[index] = true;
+
+            for neighbor in &graph[index] {
+                let new_distance = dist + neighbor.distance;
+                let neighbor_distance = self.distance[neighbor.index].unwrap_or(INF) as usize;
+
+                if new_distance < neighbor_distance {
+                    self.distance[neighbor.index] = Some(new_distance as i32);
+                    self.predecessor[neighbor.index] = Some(index);
+                    self.heap.push(Node { index: neighbor.index, distance: new_distance });
+                }
+            }
+        }
+    }
+
+    /// Same as `BestPath::shortest_path`, but each leg's Dijkstra search and path
+    /// reconstruction reuse this handle's buffers instead of allocating new ones.
+    /// The per-call matrix/graph construction and `find_connected_targets` check
+    /// shared with the allocating version, and the returned `Direction` vectors,
+    /// are still allocated on every call.
+    pub fn shortest_path(&mut self, robot: &mut impl Runnable, world: &mut World, nodi_conosciuti: &Vec<((i32, i32), Tile)>, nodi_interesse: Vec<(i32, i32)>, starting_node: (i32, i32), discover: bool) -> Vec<Vec<Direction>> {
+        let (_matrix, _minx, _miny, _discovered, coordinates, matrix_node, target_nodes, start) = prepare_query(robot, world, nodi_conosciuti, nodi_interesse, starting_node, discover, DiscoveryStrategy::Exhaustive, CostMode::Energy);
+
+        let mut remaining = find_connected_targets(&matrix_node, start, &target_nodes);
+        let mut current = start;
+        let mut final_path: Vec<Vec<Direction>> = Vec::new();
+
+        while !remaining.is_empty() {
+            self.dijkstra(&matrix_node, current);
+
+            let best = remaining.iter().cloned().filter(|&t| self.distance[t].is_some()).min_by_key(|&t| self.distance[t].unwrap());
+            let Some(best) = best else { break };
+
+            self.path_buffer.clear();
+            let mut node = best;
+            self.path_buffer.push(node);
+            while let Some(prev) = self.predecessor[node] {
+                self.path_buffer.push(prev);
+                node = prev;
+            }
+            self.path_buffer.reverse();
+
+            if self.path_buffer.len() > 1 {
+                current = *self.path_buffer.last().unwrap();
+                if let Ok(directions) = path_to_directions(&coordinates, &self.path_buffer) {
+                    final_path.push(directions);
+                }
+            }
+            remaining.retain(|&x| x != best);
+        }
+
+        if final_path.is_empty() {
+            vec![vec![]]
+        } else {
+            final_path
+        }
+    }
+}
+
+/// Lower is more attractive: combines the Manhattan distance to `target` (greedy
+/// bias) with the pheromone already deposited on `pos` (accumulated evidence
+/// that this direction has paid off before).
+fn frontier_score(pos: (i32, i32), target: (i32, i32), pheromones: &PheromoneMap) -> f32 {
+    let distance = ((pos.0 - target.0).abs() + (pos.1 - target.1).abs()) as f32;
+    distance - pheromones.level(pos)
+}
+
+/// Finds the shortest path from `from` to `to` through `known` tiles only (plain
+/// Dijkstra over `(i32, i32)` positions rather than the usual label-indexed
+/// graph, since the explored region has no fixed bounding matrix yet) and
+/// returns just the first step as a `Direction`.
+fn step_toward_known(known: &HashMap<(i32, i32), Tile>, from: (i32, i32), to: (i32, i32)) -> Option<Direction> {
+    let mut dist: HashMap<(i32, i32), i32> = HashMap::new();
+    let mut prev: HashMap<(i32, i32), (i32, i32)> = HashMap::new();
+    let mut heap = BinaryHeap::new();
+
+    dist.insert(from, 0);
+    heap.push(Reverse((0, from)));
+
+    while let Some(Reverse((d, pos))) = heap.pop() {
+        if pos == to {
+            break;
+        }
+        if d > *dist.get(&pos).unwrap_or(&INF) {
+            continue;
+        }
+        let (x, y) = pos;
+        for neighbor in [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)] {
+            let Some(tile) = known.get(&neighbor) else { continue };
+            if !is_wakable(tile) {
+                continue;
+            }
+            let new_dist = d + get_cost(tile) as i32;
+            if new_dist < *dist.get(&neighbor).unwrap_or(&INF) {
+                dist.insert(neighbor, new_dist);
+                prev.insert(neighbor, pos);
+                heap.push(Reverse((new_dist, neighbor)));
+            }
+        }
+    }
+
+    // risalgo i predecessori finché non trovo il passo immediatamente successivo a `from`
+    let mut current = to;
+    while let Some(&parent) = prev.get(&current) {
+        if parent == from {
+            return direction_between(parent, current);
+        }
+        current = parent;
+    }
+    None
+}
+
+/// Converts a single-step coordinate delta into its `Direction`, or `None` if the
+/// two positions are not 4-connected neighbours.
+fn direction_between(from: (i32, i32), to: (i32, i32)) -> Option<Direction> {
+    match (to.0 - from.0, to.1 - from.1) {
+        (-1, 0) => Some(Direction::Up),
+        (1, 0) => Some(Direction::Down),
+        (0, -1) => Some(Direction::Left),
+        (0, 1) => Some(Direction::Right),
+        _ => None,
+    }
+}
+
+/// Held-Karp dynamic programming limit: above this many targets we fall back to a
+/// cheaper heuristic rather than pay the `O(2^n * n^2)` exact cost.
+const HELD_KARP_LIMIT: usize = 12;
+
+/// Solves the open-path TSP over `start` and `targets`: visit every target exactly
+/// once, starting at `start`, minimizing total energy cost.
+///
+/// Builds the all-pairs cost matrix between `start` and `targets` by running the
+/// existing `find_shortest_paths` once per node (itself a `dijkstra` expansion
+/// plus path reconstruction), then fills `dp[mask][j]` = minimum cost of a route
+/// that starts at `start`, visits exactly the targets in `mask`, and ends at
+/// target `j`, via the recurrence
+/// `dp[mask][j] = min over k in mask\{j} of dp[mask\{j}][k] + cost(k, j)`.
+/// Back-pointers are kept to reconstruct both the visiting order and the
+/// concatenated node path.
+///
+/// Falls back to nearest-neighbor + 2-opt refinement (`nearest_neighbor_tour`)
+/// when `targets.len() > HELD_KARP_LIMIT`, since Held-Karp's `O(2^n * n^2)` blows
+/// up past that.
+fn held_karp_tour(graph: &Vec<Vec<Node>>, start: usize, targets: &Vec<usize>) -> Option<(Vec<usize>, Vec<usize>)> {
+    let n = targets.len();
+    if n == 0 {
+        return None;
+    }
+
+    // matrice dei costi e dei percorsi fra start, ogni target, e tra i target stessi
+    let mut nodes = vec![start];
+    nodes.extend(targets.iter().cloned());
+
+    let mut dist: Vec<Vec<Option<i32>>> = vec![vec![None; nodes.len()]; nodes.len()];
+    let mut paths: Vec<Vec<Option<Vec<usize>>>> = vec![vec![None; nodes.len()]; nodes.len()];
+
+    for (i, &from) in nodes.iter().enumerate() {
+        let others: Vec<usize> = nodes.iter().enumerate().filter(|&(j, _)| j != i).map(|(_, &node)| node).collect();
+        for result in find_shortest_paths(graph, from, &others) {
+            if result.path.is_none() {
+                continue;
+            }
+            let Some(j) = nodes.iter().position(|&node| node == result.target_node) else { continue };
+            dist[i][j] = Some(result.total_cost);
+            paths[i][j] = result.path;
+        }
+    }
+
+    if n > HELD_KARP_LIMIT {
+        return nearest_neighbor_tour(targets, &nodes, &dist, &paths);
+    }
+
+    // dp[mask][j]: costo minimo per visitare esattamente i target in mask, terminando in j (indice 1..=n)
+    let full_mask = (1usize << n) - 1;
+    let mut dp = vec![vec![None; n]; 1 << n];
+    let mut parent = vec![vec![None; n]; 1 << n];
+
+    for j in 0..n {
+        if let Some(cost) = dist[0][j + 1] {
+            dp[1 << j][j] = Some(cost);
+        }
+    }
+
+    for mask in 1..=full_mask {
+        for j in 0..n {
+            if mask & (1 << j) == 0 {
+                continue;
+            }
+            let Some(cur_cost) = dp[mask][j] else { continue };
+            for k in 0..n {
+                if mask & (1 << k) != 0 {
+                    continue;
+                }
+                if let Some(step_cost) = dist[j + 1][k + 1] {
+                    let new_mask = mask | (1 << k);
+                    let new_cost = cur_cost + step_cost;
+                    if dp[new_mask][k].map_or(true, |best| new_cost < best) {
+                        dp[new_mask][k] = Some(new_cost);
+                        parent[new_mask][k] = Some(j);
+                    }
+                }
+            }
+        }
+    }
+
+    let (best_j, _) = (0..n).filter_map(|j| dp[full_mask][j].map(|c| (j, c))).min_by_key(|&(_, c)| c)?;
+
+    // ricostruisco l'ordine di visita dei target tramite i back-pointer
+    let mut order_idx = vec![];
+    let mut mask = full_mask;
+    let mut j = best_j;
+    loop {
+        order_idx.push(j);
+        let prev = parent[mask][j];
+        mask &= !(1 << j);
+        match prev {
+            Some(p) => j = p,
+            None => break,
+        }
+    }
+    order_idx.reverse();
+
+    let order: Vec<usize> = order_idx.iter().map(|&i| targets[i]).collect();
+
+    // espando la sequenza di target in un percorso concatenato di nodi
+    let mut full_path = vec![];
+    let mut prev_index = 0; // indice di `nodes`, parto da start
+    for &idx in &order_idx {
+        let node_index = idx + 1;
+        let leg = paths[prev_index][node_index].clone()?;
+        if full_path.is_empty() {
+            full_path.extend(leg);
+        } else {
+            full_path.extend(leg.into_iter().skip(1));
+        }
+        prev_index = node_index;
+    }
+
+    Some((order, full_path))
+}
+
+/// Classic open-path 2-opt local search over a tour given as a sequence of
+/// `nodes` indices (with `seq[0]` fixed as the start, since the tour isn't a
+/// cycle): repeatedly reverses a segment `seq[i..=j]` whenever doing so shortens
+/// the tour, and keeps going until no reversal helps. Reversing a segment
+/// leaves every edge strictly inside it intact (just walked backward), so only
+/// the two edges bordering the segment ever need to be compared.
+fn two_opt_improve(seq: &mut Vec<usize>, dist: &Vec<Vec<Option<i32>>>) {
+    let edge_cost = |a: usize, b: usize| -> i32 { dist[a][b].unwrap_or(INF) };
+
+    let mut improved = true;
+    while improved {
+        improved = false;
+        for i in 1..seq.len().saturating_sub(1) {
+            for j in (i + 1)..seq.len() {
+                let tail_before = if j + 1 < seq.len() { edge_cost(seq[j], seq[j + 1]) } else { 0 };
+                let tail_after = if j + 1 < seq.len() { edge_cost(seq[i], seq[j + 1]) } else { 0 };
+                let before = edge_cost(seq[i - 1], seq[i]) + tail_before;
+                let after = edge_cost(seq[i - 1], seq[j]) + tail_after;
+
+                if after < before {
+                    seq[i..=j].reverse();
+                    improved = true;
+                }
+            }
+        }
+    }
+}
+
+/// Cheaper fallback for target sets too large for exact Held-Karp: hops greedily
+/// to the nearest not-yet-visited target, then polishes the resulting order with
+/// `two_opt_improve` so the greedy tour's usual quality gap gets narrowed before
+/// the caller commits to it.
+fn nearest_neighbor_tour(targets: &Vec<usize>, nodes: &Vec<usize>, dist: &Vec<Vec<Option<i32>>>, paths: &Vec<Vec<Option<Vec<usize>>>>) -> Option<(Vec<usize>, Vec<usize>)> {
+    let mut remaining: Vec<usize> = (0..targets.len()).collect();
+    let mut order_idx = vec![];
+    let mut current = 0; // indice in `nodes`
+
+    while !remaining.is_empty() {
+        let (pos, &next_idx) = remaining.iter().enumerate().filter(|&(_, &t)| dist[current][t + 1].is_some()).min_by_key(|&(_, &t)| dist[current][t + 1].unwrap())?;
+        order_idx.push(next_idx);
+        current = next_idx + 1;
+        remaining.remove(pos);
+    }
+
+    let mut seq = vec![0];
+    seq.extend(order_idx.iter().map(|&i| i + 1));
+    two_opt_improve(&mut seq, dist);
+    let order_idx: Vec<usize> = seq[1..].iter().map(|&node_index| node_index - 1).collect();
+
+    let order: Vec<usize> = order_idx.iter().map(|&i| targets[i]).collect();
+
+    let mut full_path = vec![];
+    let mut prev_index = 0;
+    for &idx in &order_idx {
+        let node_index = idx + 1;
+        let leg = paths[prev_index][node_index].clone()?;
+        if full_path.is_empty() {
+            full_path.extend(leg);
+        } else {
+            full_path.extend(leg.into_iter().skip(1));
+        }
+        prev_index = node_index;
+    }
+
+    Some((order, full_path))
+}
+
+/// Splits a concatenated tour node-path back into per-leg node sequences, one
+/// per entry of `order`, by scanning forward for each target's first
+/// occurrence after the previous leg's end. Mirrors the way `build_path`'s
+/// per-target `Vec<Direction>` entries line up one-to-one with the targets
+/// visited, so `build_path_optimized` can reuse `path_to_directions` per leg
+/// exactly like `build_path` does.
+fn split_tour_into_legs(node_path: &Vec<usize>, order: &Vec<usize>) -> Vec<Vec<usize>> {
+    let mut legs = Vec::new();
+    let mut leg_start = 0;
+
+    for &target in order {
+        let Some(rel_end) = node_path[leg_start..].iter().position(|&n| n == target) else { continue };
+        let end = leg_start + rel_end;
+        legs.push(node_path[leg_start..=end].to_vec());
+        leg_start = end;
+    }
+
+    legs
+}
+
+/// Same as `build_path`, but instead of greedily picking the cheapest single
+/// next target at each step (which can produce far-from-optimal total tours),
+/// computes the visiting order that minimizes total tour cost via
+/// `held_karp_tour` — exact Held-Karp for up to `HELD_KARP_LIMIT` targets,
+/// nearest-neighbor plus `two_opt_improve` above that — and returns the
+/// per-leg directions in that order instead.
+fn build_path_optimized(graph: &Vec<Vec<Node>>, start: usize, target_nodes: Vec<usize>, coordinates: &HashMap<usize, (usize, usize)>) -> Result<Vec<Vec<Direction>>, &'static str> {
+    if target_nodes.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let (order, node_path) = match held_karp_tour(graph, start, &target_nodes) {
+        Some(result) => result,
+        None => return Ok(vec![vec![]]),
+    };
+
+    let mut final_path = Vec::new();
+    for leg in split_tour_into_legs(&node_path, &order) {
+        final_path.push(path_to_directions(coordinates, &leg)?);
+    }
+
+    Ok(final_path)
+}
+
+/// Computes the shortest-path distance between every pair in `nodes`, the same
+/// way `held_karp_tour` builds its cost matrix: one `find_shortest_paths`
+/// expansion per node of `nodes`, against the full `graph`, not just the
+/// direct edges between members of `nodes` themselves.
+///
+/// A sparse set of targets (the common case) rarely has a real grid edge
+/// directly between two of them, so restricting the search to direct edges —
+/// as a plain Floyd-Warshall over just `nodes` would — returns `None` for
+/// almost every pair instead of the true distance. Running a full expansion
+/// per node instead costs `O(nodes.len())` Dijkstra searches over `graph`,
+/// which for a handful of targets is far cheaper than the `O(|V|^3)` of
+/// Floyd-Warshall over the whole grid. `dist[i][j]` is indexed by position in
+/// `nodes`, not by graph node index; pairs that aren't connected — including
+/// the all-walls case — are left as `None`.
+fn pairwise_distances(graph: &Vec<Vec<Node>>, nodes: &Vec<usize>) -> Vec<Vec<Option<i32>>> {
+    let n = nodes.len();
+    let mut dist: Vec<Vec<Option<i32>>> = vec![vec![None; n]; n];
+
+    for (i, &from) in nodes.iter().enumerate() {
+        dist[i][i] = Some(0);
+        let others: Vec<usize> = nodes.iter().enumerate().filter(|&(j, _)| j != i).map(|(_, &node)| node).collect();
+        for result in find_shortest_paths(graph, from, &others) {
+            let Some(_) = result.path else { continue };
+            let Some(j) = nodes.iter().position(|&node| node == result.target_node) else { continue };
+            dist[i][j] = Some(result.total_cost);
+        }
+    }
+
+    dist
 }
 
 const INF: i32 = std::i32::MAX;
@@ -240,8 +1124,46 @@ impl PartialOrd for Node {
 
 }
 */
+/// Shared setup every `BestPath`/`PathBuffers` query entry point needs before it
+/// can search: swaps the two coordinates of every `nodi_interesse` entry to
+/// match the assembled matrix's layout, discovers/loads the relevant tiles via
+/// `from_vec_to_matrix`, offsets everything into the matrix's local coordinate
+/// space, and builds the node graph via `change_matrix`. `discovery_strategy`
+/// and `cost_mode` are left to the caller since not every entry point wants
+/// `DiscoveryStrategy::Exhaustive` / `CostMode::Energy`.
+///
+/// # Returns
+///
+/// `(matrix, minx, miny, newly_discovered, coordinates, matrix_node,
+/// target_nodes, start)` — the first four are `from_vec_to_matrix`'s own
+/// outputs verbatim (only a few callers need the raw `matrix` or the
+/// offset/discovery bookkeeping beyond building the graph), `coordinates` maps
+/// node indices back to local `(row, col)`, and `matrix_node`/`target_nodes`/
+/// `start` are `change_matrix`'s graph. Callers still run
+/// `find_connected_targets` themselves, since not all of them want it applied
+/// the same way.
+fn prepare_query(robot: &mut impl Runnable, world: &mut World, nodi_conosciuti: &Vec<((i32, i32), Tile)>, nodi_interesse: Vec<(i32, i32)>, starting_node: (i32, i32), discover: bool, discovery_strategy: DiscoveryStrategy, cost_mode: CostMode) -> (Vec<Vec<Tile>>, i32, i32, Vec<((i32, i32), Tile)>, HashMap<usize, (usize, usize)>, Vec<Vec<Node>>, Vec<usize>, usize) {
+    let mut nodi_interesse_swapped: Vec<(i32, i32)> = vec![];
+    for nodo in &nodi_interesse {
+        nodi_interesse_swapped.push((nodo.1, nodo.0));
+    }
+    let corrected_starting_node = (starting_node.0, starting_node.1);
+
+    let (matrix, minx, miny, newly_discovered) = from_vec_to_matrix(robot, world, nodi_conosciuti, &nodi_interesse_swapped, corrected_starting_node, discover, discovery_strategy);
+    let mut nodi_interesse_corrected: Vec<(i32, i32)> = vec![];
+    for nodo in &nodi_interesse_swapped {
+        nodi_interesse_corrected.push((nodo.0 - minx, nodo.1 - miny));
+    }
+
+    let coordinates = get_coordinates(&matrix);
+    let corrected_starting_node = (corrected_starting_node.1 - minx, corrected_starting_node.0 - miny);
+    let (matrix_node, target_nodes, start) = change_matrix(matrix.clone(), nodi_interesse_corrected, corrected_starting_node, weather_multiplier(world), cost_mode);
+
+    (matrix, minx, miny, newly_discovered, coordinates, matrix_node, target_nodes, start)
+}
+
 // funzione per convertire il vettore dei tiles in una matrice + parte riempitiva
-fn from_vec_to_matrix(robot: &mut impl Runnable, world: &mut World, nodi_conosciuti: &Vec<((i32, i32), Tile)>, nodi_interesse: &Vec<(i32, i32)>, starting_node: (i32, i32), discover: bool) -> (Vec<Vec<Tile>>, i32, i32) {
+fn from_vec_to_matrix(robot: &mut impl Runnable, world: &mut World, nodi_conosciuti: &Vec<((i32, i32), Tile)>, nodi_interesse: &Vec<(i32, i32)>, starting_node: (i32, i32), discover: bool, discovery_strategy: DiscoveryStrategy) -> (Vec<Vec<Tile>>, i32, i32, Vec<((i32, i32), Tile)>) {
     // se discover == true, riempio la matrice con le discover
 
     if nodi_interesse.len() == 0{
@@ -310,56 +1232,160 @@ fn from_vec_to_matrix(robot: &mut impl Runnable, world: &mut World, nodi_conosci
 
 
     if !discover{
-        return (matrix, i_min_x, i_min_y);
-    }
-
-    // parte vecchia
-    let mut n_discover = 0;
-    let matrix_len = mask_matrix.len();
-    let mut new_matrix: Vec<Vec<(Tile, bool)>> = Vec::with_capacity(matrix_len);
-
-    for i in 0..matrix_len {
-        let mask_matrix_copy = mask_matrix.clone();
-        let row = mask_matrix_copy.get(i).unwrap();
-        let row_len = row.len();
-        let mut new_row: Vec<(Tile, bool)> = Vec::with_capacity(row_len);
-
-        for j in 0..row_len {
-            let (val, known) = row.get(j).unwrap();
-            if !known {
-                // scopro le celle attorno. Se almeno una è walkable prendo il suo valore e lo salvo (il più grande)
-                let neighbor = show_neighbor(&mask_matrix, i as i32, j as i32);
-                let max_val = find_max_in_tuple(neighbor);
-                // se max val è None, faccio una disover e salvo nella new_matrix
-                if max_val.is_none() {
-                    n_discover+=1;
-                    let mut coordinates = [(i,j)];
-                    let new_val = (discover_tiles(robot, world, &mut coordinates).unwrap().get(&(i,j)).unwrap().clone().unwrap(), true);
-                    new_row.push(new_val.clone());
-                    mask_matrix[i][j] = new_val.clone();
-                    // TODO: savare i tile scoperti e ritornarli all'utente
-                } else{
-                    new_row.push((max_val.unwrap(), false));
+        return (matrix, i_min_x, i_min_y, vec![]);
+    }
+
+    let mut newly_discovered: Vec<((i32, i32), Tile)> = Vec::new();
+
+    match discovery_strategy {
+        DiscoveryStrategy::Exhaustive => {
+            // parte vecchia
+            let mut n_discover = 0;
+            let matrix_len = mask_matrix.len();
+            let mut new_matrix: Vec<Vec<(Tile, bool)>> = Vec::with_capacity(matrix_len);
+
+            for i in 0..matrix_len {
+                let mask_matrix_copy = mask_matrix.clone();
+                let row = mask_matrix_copy.get(i).unwrap();
+                let row_len = row.len();
+                let mut new_row: Vec<(Tile, bool)> = Vec::with_capacity(row_len);
+
+                for j in 0..row_len {
+                    let (val, known) = row.get(j).unwrap();
+                    if !known {
+                        // scopro le celle attorno. Se almeno una è walkable prendo il suo valore e lo salvo (il più grande)
+                        let neighbor = show_neighbor(&mask_matrix, i as i32, j as i32);
+                        let max_val = find_max_in_tuple(neighbor);
+                        // se max val è None, faccio una disover e salvo nella new_matrix
+                        if max_val.is_none() {
+                            n_discover+=1;
+                            let mut coordinates = [(i,j)];
+                            let new_val = (discover_tiles(robot, world, &mut coordinates).unwrap().get(&(i,j)).unwrap().clone().unwrap(), true);
+                            new_row.push(new_val.clone());
+                            mask_matrix[i][j] = new_val.clone();
+                            newly_discovered.push(((j as i32 + i_min_x, i as i32 + i_min_y), new_val.0.clone()));
+                        } else{
+                            new_row.push((max_val.unwrap(), false));
+                        }
+                    } else {
+                        new_row.push((val.clone(), true));
+                    }
                 }
-            } else {
-                new_row.push((val.clone(), true));
+                new_matrix.push(new_row);
             }
-        }
-        new_matrix.push(new_row);
-    }
 
-    // ricostruisco la matrice rimuovendo la dupla
-    let mut to_ret: Vec<Vec<Tile>> = vec![];
-    for i in i_min_x..=i_max_x{
-        let mut row: Vec<Tile> = vec![];
-        for j in i_min_y..=i_max_y {
-            row.push(new_matrix[(i - i_min_x) as usize][(j - i_min_y) as usize].0.clone());
+            mask_matrix = new_matrix;
         }
-        to_ret.push(row);
-    }
+        DiscoveryStrategy::Frontier => {
+            let rows = mask_matrix.len();
+            let cols = if rows == 0 { 0 } else { mask_matrix[0].len() };
+
+            let start_local = ((starting_node.0 - i_min_y) as usize, (starting_node.1 - i_min_x) as usize);
+
+            // la cella di partenza del robot è percorribile per definizione ma non è
+            // detto che compaia in `nodi_conosciuti`: senza seminarla qui il fronte
+            // di esplorazione parte vuoto e il ciclo sotto non scopre mai nulla
+            if !mask_matrix[start_local.0][start_local.1].1 {
+                let mut coordinates = [start_local];
+                let tile = discover_tiles(robot, world, &mut coordinates).unwrap().get(&start_local).unwrap().clone().unwrap();
+                newly_discovered.push(((start_local.1 as i32 + i_min_x, start_local.0 as i32 + i_min_y), tile.clone()));
+                mask_matrix[start_local.0][start_local.1] = (tile, true);
+            }
 
-    return (to_ret, i_min_x, i_min_y);
-}
+            let mut remaining: Vec<(usize, usize)> = nodi_interesse.iter()
+                .map(|(x, y)| ((*y - i_min_y) as usize, (*x - i_min_x) as usize))
+                .collect();
+
+            loop {
+                remaining.retain(|&target| !reaches_via_known_walkable(&mask_matrix, rows, cols, start_local, target));
+                if remaining.is_empty() {
+                    break;
+                }
+
+                // fronte di esplorazione: celle sconosciute adiacenti (Von Neumann) ad almeno una cella nota e percorribile
+                let mut frontier: Vec<(usize, usize)> = Vec::new();
+                for r in 0..rows {
+                    for c in 0..cols {
+                        if mask_matrix[r][c].1 {
+                            continue;
+                        }
+                        let borders_known_walkable = von_neumann_neighbours((r, c), rows, cols).iter()
+                            .any(|&(nr, nc)| mask_matrix[nr][nc].1 && is_wakable(&mask_matrix[nr][nc].0));
+                        if borders_known_walkable {
+                            frontier.push((r, c));
+                        }
+                    }
+                }
+
+                if frontier.is_empty() {
+                    // nessun altro fronte raggiungibile: i target rimanenti non sono connessi
+                    break;
+                }
+
+                let mut coordinates = frontier.clone();
+                let discovered = discover_tiles(robot, world, &mut coordinates).unwrap();
+                for (r, c) in frontier {
+                    if let Some(Some(tile)) = discovered.get(&(r, c)) {
+                        mask_matrix[r][c] = (tile.clone(), true);
+                        newly_discovered.push(((c as i32 + i_min_x, r as i32 + i_min_y), tile.clone()));
+                    }
+                }
+            }
+
+            // le celle ancora sconosciute (mai attraversabili da nessun percorso utile) prendono una stima
+            // dai vicini invece di essere scoperte una ad una, così restano fuori dal conteggio delle discover
+            for r in 0..rows {
+                for c in 0..cols {
+                    if !mask_matrix[r][c].1 {
+                        if let Some(guess) = find_max_in_tuple(show_neighbor(&mask_matrix, r as i32, c as i32)) {
+                            mask_matrix[r][c].0 = guess;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // ricostruisco la matrice rimuovendo la dupla
+    let mut to_ret: Vec<Vec<Tile>> = vec![];
+    for i in i_min_x..=i_max_x{
+        let mut row: Vec<Tile> = vec![];
+        for j in i_min_y..=i_max_y {
+            row.push(mask_matrix[(i - i_min_x) as usize][(j - i_min_y) as usize].0.clone());
+        }
+        to_ret.push(row);
+    }
+
+    return (to_ret, i_min_x, i_min_y, newly_discovered);
+}
+
+/// BFS over cells already known to be walkable, used by `DiscoveryStrategy::Frontier`
+/// to tell whether `start` can already reach `target` without discovering anything
+/// new — once true for every node of interest, the frontier loop stops growing.
+fn reaches_via_known_walkable(mask_matrix: &Vec<Vec<(Tile, bool)>>, rows: usize, cols: usize, start: (usize, usize), target: (usize, usize)) -> bool {
+    if !mask_matrix[start.0][start.1].1 || !is_wakable(&mask_matrix[start.0][start.1].0) {
+        return false;
+    }
+
+    let mut visited = vec![vec![false; cols]; rows];
+    let mut queue = std::collections::VecDeque::new();
+    visited[start.0][start.1] = true;
+    queue.push_back(start);
+
+    while let Some(pos) = queue.pop_front() {
+        if pos == target {
+            return true;
+        }
+        for neighbour in von_neumann_neighbours(pos, rows, cols) {
+            if !visited[neighbour.0][neighbour.1] && mask_matrix[neighbour.0][neighbour.1].1 && is_wakable(&mask_matrix[neighbour.0][neighbour.1].0) {
+                visited[neighbour.0][neighbour.1] = true;
+                queue.push_back(neighbour);
+            }
+        }
+    }
+
+    false
+}
 
 // funzione per ritornare il costo più alto delle tiles adiacenti
 fn find_max_in_tuple(tuple: (Option<Tile>, Option<Tile>, Option<Tile>, Option<Tile>, Option<Tile>, Option<Tile>, Option<Tile>, Option<Tile>)) -> Option<Tile> {
@@ -427,7 +1453,7 @@ fn scan_matrix(matrix: &Vec<Vec<(Tile, bool)>>, x: i32, y: i32)->Option<Tile>{
 
 
 // passo da matrice a grafo
-fn change_matrix(matrix_tile: Vec<Vec<Tile>>, nodi_dinteresse: Vec<(i32, i32)>, startin_node: (i32, i32)) -> (Vec<Vec<Node>>, Vec<usize>, usize) {
+fn change_matrix(matrix_tile: Vec<Vec<Tile>>, nodi_dinteresse: Vec<(i32, i32)>, startin_node: (i32, i32), weather_multiplier: f32, cost_mode: CostMode) -> (Vec<Vec<Node>>, Vec<usize>, usize) {
     let rows = matrix_tile.len();
     let cols = matrix_tile[0].len();
     let mut matrix_node = vec![vec![]; rows * cols];
@@ -452,7 +1478,7 @@ fn change_matrix(matrix_tile: Vec<Vec<Tile>>, nodi_dinteresse: Vec<(i32, i32)>,
             }
 
             if is_walkable {
-                let neighbours = get_neighbours(&matrix_tile,x,y,label_node, &tile);
+                let neighbours = get_neighbours(&matrix_tile,x,y,label_node, &tile, weather_multiplier, cost_mode);
                 for i in neighbours {
                     matrix_node[label_node].push(i);
                 }
@@ -477,62 +1503,76 @@ fn is_wakable (tile: &Tile) -> bool {
     }
 }
 
-/// Returns the cost of moving to a Tile with higher elevation
-fn get_cost_elevation (tile_arrive: &Tile, tile_start: &Tile) -> usize {
+/// Returns the energy multiplier robotics_lib applies to movement under the
+/// world's current weather, read from its `EnvironmentalConditions`. Matches the
+/// `go()` cost rules: `Rainy` makes every step more tiring, other conditions are
+/// left at the baseline multiplier until the crate exposes a principled cost for
+/// them.
+fn weather_multiplier(world: &World) -> f32 {
+    match look_at_sky(world).get_weather_condition() {
+        WeatherType::Rainy => 1.5,
+        _ => 1.0,
+    }
+}
+
+/// Returns the cost of moving to a Tile with higher elevation, scaled by the
+/// current weather's energy multiplier (climbing in the rain costs more).
+fn get_cost_elevation (tile_arrive: &Tile, tile_start: &Tile, weather_multiplier: f32) -> usize {
     if tile_arrive.elevation <= tile_start.elevation {
         return 0;
     }
-    (tile_arrive.elevation - tile_start.elevation).pow(2)
+    (((tile_arrive.elevation - tile_start.elevation).pow(2)) as f32 * weather_multiplier) as usize
 }
 
+/// Picks what an edge weight represents: `Energy` (the default, and the only
+/// mode before this) uses the real per-move energy cost from `get_cost` and
+/// `get_cost_elevation`, so `shortest_path` minimizes how tiring the route is.
+/// `Steps` makes every walkable edge cost exactly 1, so the search instead
+/// minimizes the number of moves — useful when a robot cares about turn count
+/// more than energy, or wants a quick upper bound unaffected by terrain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CostMode {
+    Energy,
+    Steps,
+}
 
 /// Returns a vector of Node made by the neighbours of the tile given as a parameter in the function if they are walkable
-fn get_neighbours (matrix_tile: &Vec<Vec<Tile>>, x: usize, y: usize, value: usize, tile: &Tile) -> Vec<Node> {
+fn get_neighbours (matrix_tile: &Vec<Vec<Tile>>, x: usize, y: usize, value: usize, tile: &Tile, weather_multiplier: f32, cost_mode: CostMode) -> Vec<Node> {
     let rows = matrix_tile.len();
     let cols = matrix_tile[0].len();
     let mut vec = vec![];
+
+    // in CostMode::Steps ogni passo camminabile costa 1, a prescindere da tile e elevazione
+    let edge_cost = |dest: &Tile| -> usize {
+        match cost_mode {
+            CostMode::Steps => 1,
+            CostMode::Energy if dest.elevation == 0 => get_cost(dest),
+            CostMode::Energy => get_cost(dest) + get_cost_elevation(dest, tile, weather_multiplier),
+        }
+    };
+
     // Tile at bottom
     if (x as i32-1) >= 0 && (x as i32-1) < rows as i32 && (y) < cols {
         if is_wakable(&matrix_tile[x-1][y]) {
-            if matrix_tile[x-1][y].elevation == 0 {
-                vec.push(Node::new(value-cols,get_cost(&matrix_tile[x-1][y])));
-            }
-            else {
-                vec.push(Node::new(value-cols,get_cost(&matrix_tile[x-1][y]) + get_cost_elevation(&matrix_tile[x-1][y],tile)));
-            }
+            vec.push(Node::new(value-cols, edge_cost(&matrix_tile[x-1][y])));
         }
     }
     // Tile at right
     if (x) < rows && (y+1) < cols {
         if is_wakable(&matrix_tile[x][y+1]) {
-            if matrix_tile[x][y+1].elevation == 0 {
-                vec.push(Node::new(value+1,get_cost(&matrix_tile[x][y+1])));
-            }
-            else {
-                vec.push(Node::new(value+1,get_cost(&matrix_tile[x][y+1]) + get_cost_elevation(&matrix_tile[x][y+1],tile)));
-            }
+            vec.push(Node::new(value+1, edge_cost(&matrix_tile[x][y+1])));
         }
     }
     // Tile at top
     if (x+1) < rows && (y) < cols {
         if is_wakable(&matrix_tile[x+1][y]) {
-            if matrix_tile[x+1][y].elevation == 0 {
-                vec.push(Node::new(value+cols,get_cost(&matrix_tile[x+1][y])));
-            }
-            else {
-                vec.push(Node::new(value+cols,get_cost(&matrix_tile[x+1][y]) + get_cost_elevation(&matrix_tile[x+1][y],tile)));
-            }
+            vec.push(Node::new(value+cols, edge_cost(&matrix_tile[x+1][y])));
         }
     }
     // Tile at left
     if (x) < rows && (y as i32-1) >= 0 && (y as i32-1) < cols as i32 {
         if is_wakable(&matrix_tile[x][y-1]) {
-            if matrix_tile[x][y-1].elevation == 0 {
-                vec.push(Node::new(value-1,get_cost(&matrix_tile[x][y-1])));
-            }
-            else {
-                vec.push(Node::new(value-1,get_cost(&matrix_tile[x][y-1]) + get_cost_elevation(&matrix_tile[x][y-1],tile)));
-            }
+            vec.push(Node::new(value-1, edge_cost(&matrix_tile[x][y-1])));
         }
     }
     vec
@@ -603,6 +1643,351 @@ fn dijkstra(graph: &Vec<Vec<Node>>, start: usize) -> (Vec<Option<i32>>, Vec<Opti
     (distance, predecessor)
 }
 
+/// Same as `dijkstra`, but never relaxes an edge whose tentative distance would
+/// push it past `max_budget` — a node beyond the budget is simply left
+/// unreached (`None`) instead of settled, so callers can tell "too expensive"
+/// apart from "reached, just costly" the same way they already tell apart
+/// "unreachable" from "reached".
+fn dijkstra_with_budget(graph: &Vec<Vec<Node>>, start: usize, max_budget: Option<i32>) -> (Vec<Option<i32>>, Vec<Option<usize>>) {
+    let mut distance: Vec<Option<i32>> = vec![None; graph.len()];
+    let mut predecessor: Vec<Option<usize>> = vec![None; graph.len()];
+    let mut visited: Vec<bool> = vec![false; graph.len()];
+
+    distance[start] = Some(0);
+    let mut heap = BinaryHeap::new();
+    heap.push(Node { index: start, distance: 0 });
+
+    while let Some(Node { index, distance: dist }) = heap.pop() {
+        if visited[index] {
+            continue;
+        }
+        visited[index] = true;
+
+        for neighbor in &graph[index] {
+            let new_distance = dist + neighbor.distance;
+            if let Some(budget) = max_budget {
+                if new_distance as i32 > budget {
+                    continue;
+                }
+            }
+
+            let neighbor_distance: usize = distance[neighbor.index].unwrap_or(INF) as usize;
+
+            if new_distance < neighbor_distance {
+                distance[neighbor.index] = Some(new_distance as i32);
+                predecessor[neighbor.index] = Some(index);
+                heap.push(Node { index: neighbor.index, distance: new_distance });
+            }
+        }
+    }
+
+    (distance, predecessor)
+}
+
+/// Returns the cheapest walkable tile cost in the matrix, used to keep the A*
+/// heuristic admissible (it can never overestimate the true remaining cost).
+fn min_step_cost(matrix: &Vec<Vec<Tile>>) -> usize {
+    matrix.iter()
+        .flatten()
+        .filter(|tile| is_wakable(tile))
+        .map(get_cost)
+        .min()
+        .unwrap_or(1)
+}
+
+/// Manhattan distance between two node indices, decoded directly from `cols`
+/// (`n` maps to grid coordinates `(n / cols, n % cols)`) and scaled by
+/// `min_step_cost`. Since no walkable move can cost less than `min_step_cost`,
+/// this never overestimates the true remaining cost and stays admissible;
+/// working from `cols` instead of the `coordinates` map avoids a hash lookup per
+/// node expanded, which matters in `astar`'s hot loop.
+fn heuristic(cols: usize, from: usize, to: usize, min_step_cost: usize) -> usize {
+    let (r1, c1) = (from / cols, from % cols);
+    let (r2, c2) = (to / cols, to % cols);
+    let dist = (r1 as i32 - r2 as i32).abs() + (c1 as i32 - c2 as i32).abs();
+    dist as usize * min_step_cost
+}
+
+/// Goal-directed variant of `dijkstra`: explores nodes in order of `g + h` instead
+/// of `g` alone, stopping as soon as `goal` is popped from the heap. `g` (the
+/// accumulated `distance`) is tracked separately from the heap priority so the
+/// returned distances remain true path costs.
+///
+/// `heuristic` is a plain closure rather than the hard-coded Manhattan distance,
+/// so a caller that knows more about the grid (diagonals enabled, a precomputed
+/// `coordinates` lookup, weighted terrain) can plug in its own admissible
+/// estimate of the remaining cost from a node to `goal`. It must never
+/// overestimate that cost, or the returned path is no longer guaranteed shortest.
+///
+/// Returns the same shape as `dijkstra` so `reconstruct_shortest_path` can be
+/// reused unchanged; nodes that were never reached keep a `None` distance, and
+/// nodes reached but not expanded before `goal` was popped keep a tentative one.
+fn astar_with_heuristic<H: Fn(usize) -> usize>(graph: &Vec<Vec<Node>>, start: usize, goal: usize, heuristic: H) -> (Vec<Option<i32>>, Vec<Option<usize>>) {
+    let mut distance: Vec<Option<i32>> = vec![None; graph.len()];
+    let mut predecessor: Vec<Option<usize>> = vec![None; graph.len()];
+    let mut visited: Vec<bool> = vec![false; graph.len()];
+
+    distance[start] = Some(0);
+    let mut heap = BinaryHeap::new();
+    heap.push(Node { index: start, distance: heuristic(start) });
+
+    while let Some(Node { index, distance: _ }) = heap.pop() {
+        if visited[index] {
+            continue;
+        }
+        visited[index] = true;
+
+        if index == goal {
+            break;
+        }
+
+        let g = distance[index].unwrap_or(INF) as usize;
+
+        for neighbor in &graph[index] {
+            let new_g = g + neighbor.distance;
+            let neighbor_g: usize = distance[neighbor.index].unwrap_or(INF) as usize;
+
+            if new_g < neighbor_g {
+                distance[neighbor.index] = Some(new_g as i32);
+                predecessor[neighbor.index] = Some(index);
+                let priority = new_g + heuristic(neighbor.index);
+                heap.push(Node { index: neighbor.index, distance: priority });
+            }
+        }
+    }
+
+    (distance, predecessor)
+}
+
+/// `astar_with_heuristic`, pinned to the default Manhattan-distance-over-`cols`
+/// heuristic scaled by `min_step_cost`. Kept as a thin wrapper so existing
+/// callers don't need to build a closure for the common case.
+fn astar(graph: &Vec<Vec<Node>>, start: usize, goal: usize, cols: usize, min_step_cost: usize) -> (Vec<Option<i32>>, Vec<Option<usize>>) {
+    astar_with_heuristic(graph, start, goal, |node| heuristic(cols, node, goal, min_step_cost))
+}
+
+/// Multi-target A*: treats every node in `target_nodes` as a potential goal and
+/// returns the first one popped from the heap, i.e. the nearest reachable
+/// target. `heuristic` only has to lower-bound the distance to the *nearest*
+/// target for this to stay admissible, which is exactly what `build_path_astar`
+/// passes in (the min, over remaining targets, of the Manhattan estimate) — so
+/// it slots into `build_path`'s one-target-at-a-time loop in place of a full
+/// `dijkstra` sweep over every node. Returns `None` if no target is reachable.
+fn nearest_target_astar<H: Fn(usize) -> usize>(graph: &Vec<Vec<Node>>, start: usize, target_nodes: &Vec<usize>, heuristic: H) -> Option<PathResult> {
+    let mut distance: Vec<Option<i32>> = vec![None; graph.len()];
+    let mut predecessor: Vec<Option<usize>> = vec![None; graph.len()];
+    let mut visited: Vec<bool> = vec![false; graph.len()];
+
+    distance[start] = Some(0);
+    let mut heap = BinaryHeap::new();
+    heap.push(Node { index: start, distance: heuristic(start) });
+
+    while let Some(Node { index, distance: _ }) = heap.pop() {
+        if visited[index] {
+            continue;
+        }
+        visited[index] = true;
+
+        if target_nodes.contains(&index) {
+            let total_cost = distance[index].unwrap_or(0);
+            let path = reconstruct_shortest_path(predecessor, index);
+            return Some(PathResult { path, target_node: index, total_cost });
+        }
+
+        let g = distance[index].unwrap_or(INF) as usize;
+
+        for neighbor in &graph[index] {
+            let new_g = g + neighbor.distance;
+            let neighbor_g: usize = distance[neighbor.index].unwrap_or(INF) as usize;
+
+            if new_g < neighbor_g {
+                distance[neighbor.index] = Some(new_g as i32);
+                predecessor[neighbor.index] = Some(index);
+                let priority = new_g + heuristic(neighbor.index);
+                heap.push(Node { index: neighbor.index, distance: priority });
+            }
+        }
+    }
+
+    None
+}
+
+/// Same as `build_path`, but visits each leg with `nearest_target_astar` instead
+/// of a full `dijkstra` sweep: the heuristic for a node is the minimum, over the
+/// still-unvisited targets, of its Manhattan distance to that target, which
+/// stays admissible for "reach whichever target is nearest" the same way a
+/// single-goal heuristic stays admissible for one goal. On large sparse grids
+/// this expands far fewer nodes per leg than `build_path` while still returning
+/// the same optimal per-leg paths. A leg with no reachable target stops the tour
+/// early and the legs found so far are returned.
+fn build_path_astar(graph: &Vec<Vec<Node>>, mut start: usize, mut target_nodes: Vec<usize>, coordinates: &HashMap<usize, (usize, usize)>, cols: usize, min_step_cost: usize) -> Result<Vec<Vec<Direction>>, &'static str> {
+    let mut final_path: Vec<Vec<Direction>> = Vec::new();
+
+    while !target_nodes.is_empty() {
+        let nearest_heuristic = |node: usize| {
+            target_nodes.iter().map(|&target| heuristic(cols, node, target, min_step_cost)).min().unwrap_or(0)
+        };
+
+        match nearest_target_astar(graph, start, &target_nodes, nearest_heuristic) {
+            Some(best) => {
+                if let Some(path) = &best.path {
+                    start = path.last().cloned().unwrap();
+                    let directions = path_to_directions(coordinates, path)?;
+                    final_path.push(directions);
+                    target_nodes.retain(|&x| x != best.target_node);
+                }
+            }
+            None => break,
+        }
+    }
+
+    Ok(final_path)
+}
+
+/// The four cardinal scan directions Jump Point Search jumps along, since
+/// `Direction` (and the robot underneath it) only moves orthogonally — see the
+/// note in `path_to_directions`. There is no diagonal jump phase here, unlike
+/// classic 8-connected JPS.
+const JPS_DIRECTIONS: [(i32, i32); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+
+/// `true` if `(row, col)` is in bounds and walkable in `matrix`.
+fn is_wakable_at(matrix: &Vec<Vec<Tile>>, row: i32, col: i32) -> bool {
+    if row < 0 || col < 0 {
+        return false;
+    }
+    match matrix.get(row as usize).and_then(|r| r.get(col as usize)) {
+        Some(tile) => is_wakable(tile),
+        None => false,
+    }
+}
+
+/// Scans straight from `pos` along `dir` until it hits a wall, the goal, or a
+/// *forced neighbor* — a cell with a blocked side neighbour (perpendicular to
+/// `dir`) whose next cell along `dir` on that same side is walkable again,
+/// meaning a path could only reach that side past the obstacle by turning
+/// here. That cell becomes a jump
+/// point even though no diagonal move is ever taken; the forced-neighbor test
+/// is purely an internal pruning heuristic, same as classic JPS, just adapted
+/// to a 4-connected grid.
+fn jump(matrix: &Vec<Vec<Tile>>, pos: (usize, usize), dir: (i32, i32), goal: (usize, usize)) -> Option<(usize, usize)> {
+    let (dr, dc) = dir;
+    let mut cur = (pos.0 as i32 + dr, pos.1 as i32 + dc);
+
+    loop {
+        if !is_wakable_at(matrix, cur.0, cur.1) {
+            return None;
+        }
+        let (r, c) = (cur.0 as usize, cur.1 as usize);
+        if (r, c) == goal {
+            return Some((r, c));
+        }
+
+        let forced = if dr != 0 {
+            (!is_wakable_at(matrix, cur.0, cur.1 - 1) && is_wakable_at(matrix, cur.0 + dr, cur.1 - 1))
+                || (!is_wakable_at(matrix, cur.0, cur.1 + 1) && is_wakable_at(matrix, cur.0 + dr, cur.1 + 1))
+        } else {
+            (!is_wakable_at(matrix, cur.0 - 1, cur.1) && is_wakable_at(matrix, cur.0 - 1, cur.1 + dc))
+                || (!is_wakable_at(matrix, cur.0 + 1, cur.1) && is_wakable_at(matrix, cur.0 + 1, cur.1 + dc))
+        };
+        if forced {
+            return Some((r, c));
+        }
+
+        // Sulla griglia aperta (niente muri, niente vicini forzati) una scansione
+        // verticale/orizzontale non si fermerebbe mai prima del bordo della
+        // matrice, perché nessuna delle due condizioni sopra scatta mai. La regola
+        // standard di JPS copre anche questo caso: una scansione verticale (la
+        // riga cambia) diventa comunque un jump point non appena la sua riga
+        // coincide con quella del goal, e una scansione orizzontale (la colonna
+        // cambia) non appena la sua colonna coincide con quella del goal — da lì
+        // una seconda scansione perpendicolare può raggiungere il goal
+        // direttamente, chiudendo il percorso a "L".
+        if (dr != 0 && r == goal.0) || (dc != 0 && c == goal.1) {
+            return Some((r, c));
+        }
+
+        cur = (cur.0 + dr, cur.1 + dc);
+    }
+}
+
+/// Same shape as `dijkstra`/`astar` (so `reconstruct_shortest_path` still
+/// reconstructs the chain of jump points), but expands Jump Point Search jumps
+/// instead of individual tile neighbors, over the raw `matrix` grid rather than
+/// the weighted `Vec<Vec<Node>>` graph — every walkable tile costs the same to
+/// enter here, since JPS's pruning only stays correct on a uniform-cost grid.
+/// `reconstruct_shortest_path`'s result only contains jump points, not every
+/// tile crossed; expand it with `expand_jump_points` before handing it to
+/// `path_to_directions`.
+fn find_shortest_paths_jps(matrix: &Vec<Vec<Tile>>, start: usize, goal: usize, cols: usize) -> (Vec<Option<i32>>, Vec<Option<usize>>) {
+    let total = matrix.len() * cols;
+    let mut distance: Vec<Option<i32>> = vec![None; total];
+    let mut predecessor: Vec<Option<usize>> = vec![None; total];
+    let mut visited: Vec<bool> = vec![false; total];
+
+    let goal_rc = (goal / cols, goal % cols);
+
+    distance[start] = Some(0);
+    let mut heap = BinaryHeap::new();
+    heap.push(Node { index: start, distance: 0 });
+
+    while let Some(Node { index, distance: dist }) = heap.pop() {
+        if visited[index] {
+            continue;
+        }
+        visited[index] = true;
+
+        if index == goal {
+            break;
+        }
+
+        let pos = (index / cols, index % cols);
+
+        for &dir in JPS_DIRECTIONS.iter() {
+            let Some((jr, jc)) = jump(matrix, pos, dir, goal_rc) else { continue };
+            let jump_index = jr * cols + jc;
+            let steps = (jr as i32 - pos.0 as i32).abs() + (jc as i32 - pos.1 as i32).abs();
+            let new_distance = dist + steps as usize;
+            let jump_distance = distance[jump_index].unwrap_or(INF) as usize;
+
+            if new_distance < jump_distance {
+                distance[jump_index] = Some(new_distance as i32);
+                predecessor[jump_index] = Some(index);
+                heap.push(Node { index: jump_index, distance: new_distance });
+            }
+        }
+    }
+
+    (distance, predecessor)
+}
+
+/// Expands a sparse chain of jump-point indices (as returned by
+/// `reconstruct_shortest_path` over `find_shortest_paths_jps`'s predecessors)
+/// into every tile crossed between consecutive jump points, so `path_to_directions`
+/// still sees a single-step delta between each pair of entries.
+fn expand_jump_points(jump_points: &Vec<usize>, cols: usize) -> Vec<usize> {
+    let mut full_path = Vec::new();
+    for window in jump_points.windows(2) {
+        let (from, to) = (window[0], window[1]);
+        if full_path.is_empty() {
+            full_path.push(from);
+        }
+        let (r1, c1) = (from / cols, from % cols);
+        let (r2, c2) = (to / cols, to % cols);
+        let dr = (r2 as i32 - r1 as i32).signum();
+        let dc = (c2 as i32 - c1 as i32).signum();
+        let steps = (r2 as i32 - r1 as i32).abs().max((c2 as i32 - c1 as i32).abs());
+        for step in 1..=steps {
+            let r = (r1 as i32 + dr * step) as usize;
+            let c = (c1 as i32 + dc * step) as usize;
+            full_path.push(r * cols + c);
+        }
+    }
+    if full_path.is_empty() {
+        full_path.extend(jump_points.iter().cloned());
+    }
+    full_path
+}
+
 /// Reconstructs the shortest path from the start node to the target node using the predecessors vector.
 fn reconstruct_shortest_path(predecessor: Vec<Option<usize>>, target: usize) -> Option<Vec<usize>> {
     let mut path = Vec::new();
@@ -665,6 +2050,32 @@ fn find_shortest_paths(graph: &Vec<Vec<Node>>, start: usize, target_nodes: &Vec<
     results
 }
 
+/// Same as `find_shortest_paths`, but runs `dijkstra_with_budget` and drops any
+/// target whose cheapest path would cost more than `max_budget` instead of
+/// returning it at all — this is what lets `BestPath::shortest_path_with_budget`
+/// hand the caller only the targets it can actually afford.
+fn find_shortest_paths_with_budget(graph: &Vec<Vec<Node>>, start: usize, target_nodes: &Vec<usize>, max_budget: Option<i32>) -> Vec<PathResult> {
+    let (shortest_distances, predecessors) = dijkstra_with_budget(graph, start, max_budget);
+    let mut results = Vec::new();
+
+    for target_node in target_nodes {
+        let total_cost = match &shortest_distances[*target_node] {
+            None => continue,
+            Some(t) => *t,
+        };
+
+        let path = reconstruct_shortest_path(predecessors.clone(), *target_node);
+
+        results.push(PathResult {
+            path,
+            target_node: *target_node,
+            total_cost,
+        });
+    }
+
+    results
+}
+
 /////////////////////////////////////////////////////
 
 /// Costruisce percorsi tra nodi in un grafo utilizzando gli algoritmi più brevi da un punto di partenza a un insieme di nodi destinazione.
@@ -704,6 +2115,91 @@ fn build_path(graph: &Vec<Vec<Node>>, mut start: usize, mut target_nodes: Vec<us
     Ok(final_path)
 }
 
+/// Same as `dijkstra`, but reports a `SearchEvent::NodesExpanded` every
+/// `PROGRESS_REPORT_INTERVAL` pops, a `SearchEvent::InterestReached` the moment
+/// each node in `target_nodes` is settled, and stops early once `node_budget`
+/// nodes have been expanded (leaving any node not yet reached at `None`, i.e.
+/// the best-known partial result rather than a full expansion).
+fn dijkstra_with_progress(graph: &Vec<Vec<Node>>, start: usize, target_nodes: &Vec<usize>, coordinates: &HashMap<usize, (usize, usize)>, offset: (i32, i32), progress: Option<&Sender<SearchEvent>>, node_budget: Option<usize>) -> (Vec<Option<i32>>, Vec<Option<usize>>) {
+    let mut distance: Vec<Option<i32>> = vec![None; graph.len()];
+    let mut predecessor: Vec<Option<usize>> = vec![None; graph.len()];
+    let mut visited: Vec<bool> = vec![false; graph.len()];
+
+    distance[start] = Some(0);
+    let mut heap = BinaryHeap::new();
+    heap.push(Node { index: start, distance: 0 });
+
+    let mut expanded: usize = 0;
+
+    while let Some(Node { index, distance: dist }) = heap.pop() {
+        if visited[index] {
+            continue;
+        }
+        visited[index] = true;
+        expanded += 1;
+
+        if let Some(tx) = progress {
+            if expanded % PROGRESS_REPORT_INTERVAL == 0 {
+                let _ = tx.send(SearchEvent::NodesExpanded(expanded));
+            }
+            if target_nodes.contains(&index) {
+                let (row, col) = coordinates[&index];
+                let world_target = (col as i32 + offset.1, row as i32 + offset.0);
+                let _ = tx.send(SearchEvent::InterestReached { target: world_target, total_cost: dist as i32 });
+            }
+        }
+
+        if let Some(budget) = node_budget {
+            if expanded >= budget {
+                break;
+            }
+        }
+
+        for neighbor in &graph[index] {
+            let new_distance = dist + neighbor.distance;
+            let neighbor_distance: usize = distance[neighbor.index].unwrap_or(INF) as usize;
+
+            if new_distance < neighbor_distance {
+                distance[neighbor.index] = Some(new_distance as i32);
+                predecessor[neighbor.index] = Some(index);
+                heap.push(Node { index: neighbor.index, distance: new_distance });
+            }
+        }
+    }
+
+    (distance, predecessor)
+}
+
+/// Same as `build_path`, but threads an optional progress channel and
+/// node-expansion budget through each leg's search via `dijkstra_with_progress`.
+/// Returns the per-leg directions (one `Vec` per leg) together with the total energy
+/// cost of the whole tour; a target that the budget prevented from being
+/// reached is simply skipped, so the caller still gets the best-known partial
+/// path instead of an error.
+fn build_path_with_progress(graph: &Vec<Vec<Node>>, mut start: usize, mut target_nodes: Vec<usize>, coordinates: &HashMap<usize, (usize, usize)>, offset: (i32, i32), progress: Option<&Sender<SearchEvent>>, node_budget: Option<usize>) -> (Vec<Vec<Direction>>, i32) {
+    let mut final_path: Vec<Vec<Direction>> = Vec::new();
+    let mut total_cost = 0;
+
+    while !target_nodes.is_empty() {
+        let (distance, predecessor) = dijkstra_with_progress(graph, start, &target_nodes, coordinates, offset, progress, node_budget);
+
+        let best_target = target_nodes.iter().cloned().filter(|&t| distance[t].is_some()).min_by_key(|&t| distance[t].unwrap());
+
+        let Some(best_target) = best_target else { break };
+
+        if let Some(path) = reconstruct_shortest_path(predecessor, best_target) {
+            start = path.last().cloned().unwrap();
+            if let Ok(directions) = path_to_directions(coordinates, &path) {
+                total_cost += distance[best_target].unwrap_or(0);
+                final_path.push(directions);
+            }
+        }
+        target_nodes.retain(|&x| x != best_target);
+    }
+
+    (final_path, total_cost)
+}
+
 /// Converte una sequenza di nodi in una sequenza di direzioni basate sulle coordinate fornite.
 ///
 /// # Parametri
@@ -739,7 +2235,15 @@ fn path_to_directions(coordinates: &HashMap<usize, (usize, usize)>, path: &Vec<u
         // Stampa le coordinate per scopi di debug
         //println!("{:?} {:?}", current_coords, next_coords);
 
-        // Determina la direzione in base al cambiamento di coordinate
+        // Determina la direzione in base al cambiamento di coordinate.
+        //
+        // Solo le quattro direzioni cardinali sono rappresentabili qui: `Direction`
+        // viene da `robotics_lib::interface` e il robot può muoversi solo in
+        // quelle quattro direzioni, quindi non c'è un `Direction::UpLeft` (o
+        // simili) da restituire per un salto diagonale, né un modo per farlo
+        // eseguire davvero dal robot. `get_neighbours` del resto genera solo
+        // archi ortogonali, quindi un delta diagonale qui significherebbe un
+        // grafo costruito in modo inatteso, non solo un percorso non serializzabile.
         let direction = match (next_coords.0 as i32 - current_coords.0 as i32, next_coords.1 as i32 - current_coords.1 as i32) {
             (-1, 0) => Direction::Up,
             (1, 0) => Direction::Down,
@@ -755,6 +2259,22 @@ fn path_to_directions(coordinates: &HashMap<usize, (usize, usize)>, path: &Vec<u
     Ok(directions)
 }
 
+// Perché non c'è un `hex_path_to_directions`:
+//
+// Un grafo esagonale ha sei vicini per nodo invece di quattro (i sei delta
+// assiali `(+1,0),(-1,0),(0,+1),(0,-1),(+1,-1),(-1,+1)`), quindi servirebbe
+// restituire sei direzioni distinte (E, W, SE, NW, NE, SW), ognuna con la sua
+// distanza ammissibile `(|dq| + |dr| + |dq + dr|) / 2` al posto della Manhattan
+// usata da `heuristic`. Ma `Direction` viene da `robotics_lib::interface` (un
+// crate esterno che qui non possiamo estendere, per lo stesso motivo spiegato
+// sopra in `path_to_directions`) e ha solo le quattro varianti cardinali
+// `Up`/`Down`/`Left`/`Right` — non c'è modo di mappare sei vicini esagonali su
+// quattro varianti senza farne collassare almeno due sulla stessa `Direction`,
+// il che produrrebbe una mossa eseguita dal robot diversa da quella che il
+// solver intendeva. Finché il robot stesso si muove solo in quattro direzioni,
+// la metà "converti il percorso in mosse reali" di una modalità hex condivisa
+// con `find_shortest_paths`/`build_path` non è rappresentabile in questo crate.
+
 /// Trova i nodi connessi a partire da un nodo di partenza in un grafo e restituisce quelli che sono anche nei nodi di destinazione.
 ///
 /// # Parametri
@@ -818,4 +2338,540 @@ fn get_coordinates(matrix: &Vec<Vec<Tile>>) -> HashMap<usize, (usize, usize)>{
         }
     }
     hm
+}
+
+/// Chunk side length used by `PathCache`. Smaller chunks mean cheaper rebuilds
+/// on `update_tile` but a denser abstract graph; 8 matches the chunk size used
+/// by similar hierarchical pathfinders on tile-sized grids.
+const PATH_CACHE_CHUNK_SIZE: usize = 8;
+
+/// A route returned by `PathCache::find_path`: the sequence of waypoints the
+/// query passed through in the abstract gateway graph (the start, every gateway
+/// crossed, and the goal), plus its total cost. It is deliberately not yet a
+/// `Vec<Direction>` — refining every hop back into concrete tiles is deferred to
+/// `PathCache::refine` so a caller that only needs the cost (e.g. to compare
+/// several candidate goals) doesn't pay for it.
+#[derive(Debug, Clone)]
+pub struct AbstractPath {
+    pub waypoints: Vec<(usize, usize)>,
+    pub total_cost: i32,
+}
+
+/// Hierarchical path cache for repeated queries over a static tile matrix.
+///
+/// The matrix is partitioned into `PATH_CACHE_CHUNK_SIZE`-wide square chunks.
+/// Each chunk's walkable border cells that touch a neighboring chunk ("gateway"
+/// cells) are linked by their intra-chunk shortest paths, precomputed once, and
+/// gateways facing each other across a chunk boundary are linked directly. A
+/// query only has to connect `start`/`goal` to their chunk's gateways and then
+/// run Dijkstra over this much smaller abstract graph, instead of the whole
+/// matrix, which is where the speedup over rebuilding the full graph every call
+/// comes from — at the cost of routes that hug gateway cells rather than being
+/// perfectly optimal.
+///
+/// `update_tile`/`tiles_changed` keep the cache usable as the underlying map is
+/// explored further or changes: both only rebuild the chunk(s) a change
+/// actually touched, never the whole cache.
+pub struct PathCache {
+    matrix: Vec<Vec<Tile>>,
+    chunk_size: usize,
+    /// Walkable border cells of each chunk that connect to a neighboring chunk.
+    chunk_gateways: HashMap<(usize, usize), Vec<(usize, usize)>>,
+    /// Directed abstract edges between gateway cells: intra-chunk precomputed
+    /// shortest paths plus direct inter-chunk adjacencies.
+    abstract_edges: HashMap<(usize, usize), Vec<((usize, usize), i32)>>,
+}
+
+impl PathCache {
+    pub fn new(matrix: Vec<Vec<Tile>>) -> Self {
+        let mut cache = PathCache {
+            matrix,
+            chunk_size: PATH_CACHE_CHUNK_SIZE,
+            chunk_gateways: HashMap::new(),
+            abstract_edges: HashMap::new(),
+        };
+        for chunk in cache.all_chunks() {
+            cache.rebuild_chunk(chunk);
+        }
+        cache
+    }
+
+    fn rows(&self) -> usize {
+        self.matrix.len()
+    }
+
+    fn cols(&self) -> usize {
+        self.matrix.get(0).map_or(0, |row| row.len())
+    }
+
+    fn chunk_of(&self, pos: (usize, usize)) -> (usize, usize) {
+        (pos.0 / self.chunk_size, pos.1 / self.chunk_size)
+    }
+
+    fn all_chunks(&self) -> Vec<(usize, usize)> {
+        let chunk_rows = (self.rows() + self.chunk_size - 1) / self.chunk_size;
+        let chunk_cols = (self.cols() + self.chunk_size - 1) / self.chunk_size;
+        let mut chunks = vec![];
+        for cr in 0..chunk_rows {
+            for cc in 0..chunk_cols {
+                chunks.push((cr, cc));
+            }
+        }
+        chunks
+    }
+
+    /// Inclusive/exclusive `(row_start, col_start)`..`(row_end, col_end)` bounds
+    /// of a chunk, clamped to the matrix.
+    fn chunk_bounds(&self, chunk: (usize, usize)) -> ((usize, usize), (usize, usize)) {
+        let row_start = chunk.0 * self.chunk_size;
+        let col_start = chunk.1 * self.chunk_size;
+        let row_end = (row_start + self.chunk_size).min(self.rows());
+        let col_end = (col_start + self.chunk_size).min(self.cols());
+        ((row_start, col_start), (row_end, col_end))
+    }
+
+    /// Recomputes a single chunk's gateways and the abstract edges that start
+    /// from them (both intra-chunk and into neighboring chunks), leaving every
+    /// other chunk's cached data untouched.
+    fn rebuild_chunk(&mut self, chunk: (usize, usize)) {
+        let ((row_start, col_start), (row_end, col_end)) = self.chunk_bounds(chunk);
+        if row_start >= row_end || col_start >= col_end {
+            self.chunk_gateways.remove(&chunk);
+            return;
+        }
+
+        let mut gateways = vec![];
+        for row in row_start..row_end {
+            for col in col_start..col_end {
+                if !is_wakable(&self.matrix[row][col]) {
+                    continue;
+                }
+                if self.has_external_neighbour((row, col), (row_start, col_start), (row_end, col_end)) {
+                    gateways.push((row, col));
+                }
+            }
+        }
+
+        // tolgo i vecchi archi uscenti dai gateway di questo chunk prima di ricalcolarli
+        for gateway in self.chunk_gateways.get(&chunk).cloned().unwrap_or_default() {
+            self.abstract_edges.remove(&gateway);
+        }
+
+        for &gateway in &gateways {
+            let mut edges = vec![];
+
+            // collego i gateway dello stesso chunk tra loro tramite dijkstra locale
+            for &other in &gateways {
+                if other == gateway {
+                    continue;
+                }
+                if let Some((cost, _)) = local_dijkstra(&self.matrix, (row_start, col_start), (row_end, col_end), gateway, other) {
+                    edges.push((other, cost));
+                }
+            }
+
+            // collego direttamente i gateway adiacenti oltre il confine del chunk
+            for neighbor in von_neumann_neighbours(gateway, self.rows(), self.cols()) {
+                if self.chunk_of(neighbor) != chunk && is_wakable(&self.matrix[neighbor.0][neighbor.1]) {
+                    edges.push((neighbor, get_cost(&self.matrix[neighbor.0][neighbor.1]) as i32));
+                }
+            }
+
+            self.abstract_edges.insert(gateway, edges);
+        }
+
+        self.chunk_gateways.insert(chunk, gateways);
+    }
+
+    fn has_external_neighbour(&self, pos: (usize, usize), (row_start, col_start): (usize, usize), (row_end, col_end): (usize, usize)) -> bool {
+        von_neumann_neighbours(pos, self.rows(), self.cols()).into_iter().any(|(r, c)| {
+            r < row_start || r >= row_end || c < col_start || c >= col_end
+        })
+    }
+
+    /// Invalidates and rebuilds only the chunk containing `pos` after one of its
+    /// tiles changed, instead of rebuilding the whole cache.
+    pub fn update_tile(&mut self, pos: (usize, usize), tile: Tile) {
+        if pos.0 >= self.rows() || pos.1 >= self.cols() {
+            return;
+        }
+        self.matrix[pos.0][pos.1] = tile;
+        self.rebuild_chunk(self.chunk_of(pos));
+    }
+
+    /// Batched counterpart to `update_tile`: applies every `(pos, tile)` change
+    /// first, then rebuilds each chunk touched by at least one of them exactly
+    /// once. A region spanning many tiles across a handful of chunks only pays
+    /// for those chunks' rebuilds instead of one rebuild per tile, which matters
+    /// since repeated `update_tile` calls would otherwise rebuild the same
+    /// chunk over and over as its tiles change one at a time.
+    pub fn tiles_changed(&mut self, changes: &Vec<((usize, usize), Tile)>) {
+        let mut affected_chunks: Vec<(usize, usize)> = vec![];
+
+        for &(pos, tile) in changes {
+            if pos.0 >= self.rows() || pos.1 >= self.cols() {
+                continue;
+            }
+            self.matrix[pos.0][pos.1] = tile;
+            let chunk = self.chunk_of(pos);
+            if !affected_chunks.contains(&chunk) {
+                affected_chunks.push(chunk);
+            }
+        }
+
+        for chunk in affected_chunks {
+            self.rebuild_chunk(chunk);
+        }
+    }
+
+    /// Finds a route from `start` to `goal` through the abstract gateway graph:
+    /// connects `start` and `goal` to their own chunk's gateways via local
+    /// Dijkstra, then runs Dijkstra over the small combined graph. Returns
+    /// `None` if no route exists.
+    pub fn find_path(&self, start: (usize, usize), goal: (usize, usize)) -> Option<AbstractPath> {
+        if start == goal {
+            return Some(AbstractPath { waypoints: vec![start], total_cost: 0 });
+        }
+
+        let start_chunk = self.chunk_of(start);
+        let goal_chunk = self.chunk_of(goal);
+
+        // se start e goal sono nello stesso chunk, una dijkstra locale basta ed è anche più precisa
+        if start_chunk == goal_chunk {
+            let ((row_start, col_start), (row_end, col_end)) = self.chunk_bounds(start_chunk);
+            if let Some((cost, path)) = local_dijkstra(&self.matrix, (row_start, col_start), (row_end, col_end), start, goal) {
+                return Some(AbstractPath { waypoints: path, total_cost: cost });
+            }
+        }
+
+        let mut dist: HashMap<(usize, usize), i32> = HashMap::new();
+        let mut prev: HashMap<(usize, usize), (usize, usize)> = HashMap::new();
+        let mut heap = BinaryHeap::new();
+
+        dist.insert(start, 0);
+        heap.push(Reverse((0, start)));
+
+        while let Some(Reverse((d, pos))) = heap.pop() {
+            if pos == goal {
+                break;
+            }
+            if d > *dist.get(&pos).unwrap_or(&INF) {
+                continue;
+            }
+
+            // archi fuori da `pos`: se è start o un gateway già noto, collego ai gateway del suo chunk;
+            // altrimenti (il nodo goal stesso) mi connetto ai gateway del chunk del goal
+            let edges = self.edges_from(pos, goal, goal_chunk);
+
+            for (next, cost) in edges {
+                let new_dist = d + cost;
+                if new_dist < *dist.get(&next).unwrap_or(&INF) {
+                    dist.insert(next, new_dist);
+                    prev.insert(next, pos);
+                    heap.push(Reverse((new_dist, next)));
+                }
+            }
+        }
+
+        let total_cost = *dist.get(&goal)?;
+        let mut waypoints = vec![goal];
+        let mut current = goal;
+        while let Some(&parent) = prev.get(&current) {
+            waypoints.push(parent);
+            current = parent;
+        }
+        waypoints.reverse();
+
+        Some(AbstractPath { waypoints, total_cost })
+    }
+
+    /// Outgoing edges used while running Dijkstra over the abstract graph: a
+    /// precomputed gateway just looks up its cached edges, while any other
+    /// position (only ever `start` or `goal`) is connected on the fly to every
+    /// gateway of its own chunk via a local Dijkstra.
+    fn edges_from(&self, pos: (usize, usize), goal: (usize, usize), goal_chunk: (usize, usize)) -> Vec<((usize, usize), i32)> {
+        if let Some(edges) = self.abstract_edges.get(&pos) {
+            let mut edges = edges.clone();
+            if self.chunk_of(pos) == goal_chunk {
+                let ((row_start, col_start), (row_end, col_end)) = self.chunk_bounds(goal_chunk);
+                if let Some((cost, _)) = local_dijkstra(&self.matrix, (row_start, col_start), (row_end, col_end), pos, goal) {
+                    edges.push((goal, cost));
+                }
+            }
+            return edges;
+        }
+
+        let chunk = self.chunk_of(pos);
+        let ((row_start, col_start), (row_end, col_end)) = self.chunk_bounds(chunk);
+        let gateways = self.chunk_gateways.get(&chunk).cloned().unwrap_or_default();
+
+        let mut edges = vec![];
+        for gateway in gateways {
+            if let Some((cost, _)) = local_dijkstra(&self.matrix, (row_start, col_start), (row_end, col_end), pos, gateway) {
+                edges.push((gateway, cost));
+            }
+        }
+        if chunk == goal_chunk {
+            if let Some((cost, _)) = local_dijkstra(&self.matrix, (row_start, col_start), (row_end, col_end), pos, goal) {
+                edges.push((goal, cost));
+            }
+        }
+        edges
+    }
+
+    /// Expands an `AbstractPath`'s waypoints into the concrete `Direction`s
+    /// between each consecutive pair, stitching the intra-chunk segments back
+    /// together. Deferred from `find_path` so a caller that only needs the cost
+    /// doesn't pay the refinement cost.
+    pub fn refine(&self, path: &AbstractPath) -> Option<Vec<Direction>> {
+        let mut directions = vec![];
+        for pair in path.waypoints.windows(2) {
+            let (from, to) = (pair[0], pair[1]);
+            let chunk = self.chunk_of(from);
+            let ((row_start, col_start), (row_end, col_end)) = if self.chunk_of(to) == chunk {
+                self.chunk_bounds(chunk)
+            } else {
+                // un salto diretto fra gateway di chunk adiacenti è un singolo passo
+                ((0, 0), (self.rows(), self.cols()))
+            };
+            let (_, segment) = local_dijkstra(&self.matrix, (row_start, col_start), (row_end, col_end), from, to)?;
+            for step in segment.windows(2) {
+                let delta = (step[1].0 as i32 - step[0].0 as i32, step[1].1 as i32 - step[0].1 as i32);
+                directions.push(match delta {
+                    (-1, 0) => Direction::Up,
+                    (1, 0) => Direction::Down,
+                    (0, -1) => Direction::Left,
+                    (0, 1) => Direction::Right,
+                    _ => return None,
+                });
+            }
+        }
+        Some(directions)
+    }
+}
+
+/// The four Von Neumann (4-connected) neighbours of `pos` that lie within a
+/// `rows x cols` grid.
+fn von_neumann_neighbours(pos: (usize, usize), rows: usize, cols: usize) -> Vec<(usize, usize)> {
+    let mut neighbours = vec![];
+    if pos.0 > 0 {
+        neighbours.push((pos.0 - 1, pos.1));
+    }
+    if pos.0 + 1 < rows {
+        neighbours.push((pos.0 + 1, pos.1));
+    }
+    if pos.1 > 0 {
+        neighbours.push((pos.0, pos.1 - 1));
+    }
+    if pos.1 + 1 < cols {
+        neighbours.push((pos.0, pos.1 + 1));
+    }
+    neighbours
+}
+
+/// Plain Dijkstra over `(usize, usize)` matrix positions, restricted to the
+/// `[row_start, row_end) x [col_start, col_end)` bounding box. Used both to
+/// precompute a chunk's intra-chunk gateway costs and to connect an arbitrary
+/// `start`/`goal` into the cached abstract graph. Returns the total cost and
+/// the full cell-by-cell path, or `None` if `goal` is unreachable from `start`
+/// within the box.
+fn local_dijkstra(matrix: &Vec<Vec<Tile>>, (row_start, col_start): (usize, usize), (row_end, col_end): (usize, usize), start: (usize, usize), goal: (usize, usize)) -> Option<(i32, Vec<(usize, usize)>)> {
+    if start == goal {
+        return Some((0, vec![start]));
+    }
+
+    let mut dist: HashMap<(usize, usize), i32> = HashMap::new();
+    let mut prev: HashMap<(usize, usize), (usize, usize)> = HashMap::new();
+    let mut heap = BinaryHeap::new();
+
+    dist.insert(start, 0);
+    heap.push(Reverse((0, start)));
+
+    while let Some(Reverse((d, pos))) = heap.pop() {
+        if pos == goal {
+            break;
+        }
+        if d > *dist.get(&pos).unwrap_or(&INF) {
+            continue;
+        }
+
+        for neighbor in von_neumann_neighbours(pos, matrix.len(), matrix.get(0).map_or(0, |r| r.len())) {
+            if neighbor.0 < row_start || neighbor.0 >= row_end || neighbor.1 < col_start || neighbor.1 >= col_end {
+                continue;
+            }
+            if !is_wakable(&matrix[neighbor.0][neighbor.1]) {
+                continue;
+            }
+            let step_cost = get_cost(&matrix[neighbor.0][neighbor.1]) as i32 + get_cost_elevation(&matrix[neighbor.0][neighbor.1], &matrix[pos.0][pos.1], 1.0) as i32;
+            let new_dist = d + step_cost;
+            if new_dist < *dist.get(&neighbor).unwrap_or(&INF) {
+                dist.insert(neighbor, new_dist);
+                prev.insert(neighbor, pos);
+                heap.push(Reverse((new_dist, neighbor)));
+            }
+        }
+    }
+
+    let total_cost = *dist.get(&goal)?;
+    let mut path = vec![goal];
+    let mut current = goal;
+    while let Some(&parent) = prev.get(&current) {
+        path.push(parent);
+        current = parent;
+    }
+    path.reverse();
+
+    Some((total_cost, path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A flat, all-Grass matrix like the one `main.rs`'s demo world generates.
+    fn flat_grass_matrix(size: usize) -> Vec<Vec<Tile>> {
+        (0..size)
+            .map(|_| (0..size).map(|_| Tile { tile_type: TileType::Grass, content: Content::None, elevation: 0 }).collect())
+            .collect()
+    }
+
+    /// A single flat, all-Grass row `width` tiles wide — a uniform-cost 1D line
+    /// embedded in the 2D grid, so a node's column index alone determines its
+    /// distance from every other node (handy for hand-computing TSP tours).
+    fn flat_grass_row(width: usize) -> Vec<Vec<Tile>> {
+        vec![(0..width).map(|_| Tile { tile_type: TileType::Grass, content: Content::None, elevation: 0 }).collect()]
+    }
+
+    /// Plain nearest-neighbor tour cost (no 2-opt), used as the baseline that
+    /// `held_karp_tour`'s fallback path is expected to improve on.
+    fn greedy_tour_cost(start: usize, targets: &[usize], step_cost: i32) -> i32 {
+        let mut remaining: Vec<usize> = targets.to_vec();
+        let mut current = start as i32;
+        let mut cost = 0;
+        while !remaining.is_empty() {
+            let (pos, &next) = remaining.iter().enumerate().min_by_key(|&(_, &t)| (t as i32 - current).abs()).unwrap();
+            cost += (next as i32 - current).abs() * step_cost;
+            current = next as i32;
+            remaining.remove(pos);
+        }
+        cost
+    }
+
+    /// `SearchStrategy::AStar` should expand fewer nodes than Dijkstra, but it
+    /// must still find the same optimal cost on a flat, obstacle-free grid —
+    /// the same all-Grass world `main.rs` demos.
+    #[test]
+    fn astar_matches_dijkstra_cost_on_flat_grass() {
+        let matrix = flat_grass_matrix(10);
+        let start = (0, 0);
+        let goal = (9, 9);
+        let (graph, _targets, start_index) = change_matrix(matrix.clone(), vec![goal], start, 1.0, CostMode::Energy);
+        let cols = matrix[0].len();
+        let goal_index = goal.0 * cols + goal.1;
+
+        let (dijkstra_distance, _) = dijkstra(&graph, start_index);
+        let (astar_distance, _) = astar(&graph, start_index, goal_index, cols, min_step_cost(&matrix));
+
+        assert!(dijkstra_distance[goal_index].is_some(), "goal should be reachable on an open grid");
+        assert_eq!(dijkstra_distance[goal_index], astar_distance[goal_index]);
+    }
+
+    /// A tall elevation ridge should push the solver to detour through a lower
+    /// gap rather than pay `get_cost_elevation`'s quadratic cost of climbing
+    /// straight over it.
+    #[test]
+    fn path_detours_around_steep_elevation_ridge() {
+        let size = 5;
+        let mut matrix = flat_grass_matrix(size);
+        // muro di elevazione lungo la colonna 2, con un varco alla riga 3
+        for row in 0..3 {
+            matrix[row][2].elevation = 100;
+        }
+
+        let start = (0, 0);
+        let goal = (0, 4);
+        let (graph, _targets, start_index) = change_matrix(matrix.clone(), vec![goal], start, 1.0, CostMode::Energy);
+        let cols = matrix[0].len();
+        let goal_index = goal.0 * cols + goal.1;
+
+        let (_distance, predecessor) = dijkstra(&graph, start_index);
+        let path = reconstruct_shortest_path(predecessor, goal_index).expect("goal should be reachable via the gap at row 3");
+
+        for &node in &path {
+            let (row, col) = (node / cols, node % cols);
+            assert!(!(col == 2 && row < 3), "path should detour around the ridge instead of crossing it directly, crossed at ({row}, {col})");
+        }
+    }
+
+    /// `held_karp_tour`'s exact DP (used by `shortest_tour` for up to
+    /// `HELD_KARP_LIMIT` targets) should find the true optimal visiting order on
+    /// this zigzag layout, not the nearest-neighbor-greedy one — greedy always
+    /// chases whichever target is closest right now, so it sweeps the far-reaching
+    /// right side first and then has to cross back over the start (14 steps),
+    /// while sweeping the shorter left side first costs only 13.
+    #[test]
+    fn held_karp_tour_beats_nearest_neighbor_on_zigzag() {
+        let matrix = flat_grass_row(25);
+        let start_col = 10i32;
+        let start = start_col as usize; // single row, so node index == column
+
+        // offsets +1, -2, +3, -4, +5 from the start column
+        let target_cols: Vec<usize> = [1, -2, 3, -4, 5].iter().map(|offset| (start_col + offset) as usize).collect();
+        let (graph, targets, start_index) = change_matrix(matrix.clone(), target_cols.iter().map(|&c| (0, c as i32)).collect(), (0, start_col), 1.0, CostMode::Energy);
+        assert_eq!(start_index, start);
+
+        let step_cost = min_step_cost(&matrix) as i32;
+        let (order, _full_path) = held_karp_tour(&graph, start, &targets).expect("every target is reachable on an open row");
+
+        let mut visited = order.clone();
+        visited.sort();
+        let mut expected = targets.clone();
+        expected.sort();
+        assert_eq!(visited, expected, "every target should be visited exactly once");
+
+        let mut stops = vec![start];
+        stops.extend(&order);
+        let total_cost: i32 = stops.windows(2).map(|w| (w[1] as i32 - w[0] as i32).abs() * step_cost).sum();
+
+        assert_eq!(total_cost, 13 * step_cost, "should match the hand-computed optimal tour (sweep one side, then the other)");
+        assert!(total_cost < greedy_tour_cost(start, &targets, step_cost), "Held-Karp should beat plain nearest-neighbor-greedy on this zigzag");
+    }
+
+    /// Above `HELD_KARP_LIMIT`, `held_karp_tour` falls back to
+    /// `nearest_neighbor_tour` + `two_opt_improve`. Exercise that path directly
+    /// (rather than only by inspection) with more targets than the limit allows,
+    /// on the same kind of zigzag layout, and check 2-opt actually improves on
+    /// the plain greedy order instead of being a no-op.
+    #[test]
+    fn nearest_neighbor_fallback_visits_everything_and_2opt_improves_it() {
+        assert!(13 > HELD_KARP_LIMIT);
+
+        let matrix = flat_grass_row(61);
+        let start_col = 30i32;
+        let start = start_col as usize;
+
+        // an irregular spread of 13 columns whose plain nearest-neighbor-greedy
+        // order crosses itself badly enough that `two_opt_improve` measurably
+        // shortens it (verified by simulation, not hand algebra)
+        let target_cols: Vec<usize> = vec![1, 2, 5, 12, 13, 14, 27, 32, 35, 37, 38, 56, 60];
+        let (graph, targets, start_index) = change_matrix(matrix.clone(), target_cols.iter().map(|&c| (0, c as i32)).collect(), (0, start_col), 1.0, CostMode::Energy);
+        assert_eq!(start_index, start);
+        assert_eq!(targets.len(), 13);
+
+        let step_cost = min_step_cost(&matrix) as i32;
+        let (order, _full_path) = held_karp_tour(&graph, start, &targets).expect("every target is reachable on an open row");
+
+        let mut visited = order.clone();
+        visited.sort();
+        let mut expected = targets.clone();
+        expected.sort();
+        assert_eq!(visited, expected, "the nearest-neighbor fallback should still visit every target exactly once");
+
+        let mut stops = vec![start];
+        stops.extend(&order);
+        let actual_cost: i32 = stops.windows(2).map(|w| (w[1] as i32 - w[0] as i32).abs() * step_cost).sum();
+        let greedy_cost = greedy_tour_cost(start, &targets, step_cost);
+
+        assert!(actual_cost < greedy_cost, "two_opt_improve ({actual_cost}) should improve on plain greedy ({greedy_cost}), not just match it");
+    }
 }
\ No newline at end of file